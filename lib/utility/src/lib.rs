@@ -7,9 +7,17 @@ pub mod prelude {
     pub use crate::SwappableTexture;
     pub use crate::Program;
     pub use crate::Quad;
+    pub use crate::Mesh;
     pub use crate::compile_shader;
+    pub use crate::FixedTimestep;
+    pub use crate::AnimationLoop;
+    pub use crate::Frame;
 }
 
+mod timing;
+
+pub use timing::{AnimationLoop, FixedTimestep, Frame};
+
 use std::collections::HashMap;
 use std::convert::Infallible;
 
@@ -20,6 +28,9 @@ use web_sys::{WebGlFramebuffer, WebGlTexture, js_sys};
 
 type GL = WebGl2RenderingContext;
 
+const POSITION_ATTRIBUTE: u32 = 0;
+const INSTANCE_ATTRIBUTE: u32 = 1;
+
 pub trait JsView {
     unsafe fn to_js_obj(&self) -> js_sys::Object;
 }
@@ -161,6 +172,89 @@ impl BufferedTexture {
         id
     }
 
+    pub fn upload<T: JsView>(&self, format: u32, data_type: u32, src_data: T) {
+        self.context
+            .bind_texture(GL::TEXTURE_2D, self.texture.as_ref());
+        unsafe {
+            self.context
+                .tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_array_buffer_view(
+                    GL::TEXTURE_2D,
+                    0,
+                    0,
+                    0,
+                    self.width,
+                    self.height,
+                    format,
+                    data_type,
+                    Some(&src_data.to_js_obj()),
+                )
+                .expect("failed to upload texture");
+        }
+    }
+
+    // same (key, value) pairs as tex_params at creation
+    pub fn set_parameters(&self, tex_params: &[(u32, u32)]) {
+        self.context
+            .bind_texture(GL::TEXTURE_2D, self.texture.as_ref());
+        for (key, value) in tex_params {
+            self.context
+                .tex_parameteri(GL::TEXTURE_2D, *key, *value as i32);
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    // reads the whole texture back as rgba floats, bottom row first,
+    // this stalls until the gpu catches up so don't do it every frame
+    pub fn read_rgba_f32(&self) -> Vec<f32> {
+        let len = (self.width * self.height * 4) as u32;
+        let array = js_sys::Float32Array::new_with_length(len);
+        self.context
+            .bind_framebuffer(GL::FRAMEBUFFER, self.framebuffer.as_ref());
+        self.context
+            .read_pixels_with_opt_array_buffer_view(
+                0,
+                0,
+                self.width,
+                self.height,
+                GL::RGBA,
+                GL::FLOAT,
+                Some(&array),
+            )
+            .expect("failed to read texture");
+        array.to_vec()
+    }
+
+    // same as read_rgba_f32 for textures with normalized integer formats
+    pub fn read_rgba_u8(&self) -> Vec<u8> {
+        self.read_region_u8(0, 0, self.width, self.height)
+    }
+
+    // a rectangle of read_rgba_u8, cheap enough for a few pixels now and then
+    pub fn read_region_u8(&self, x: i32, y: i32, width: i32, height: i32) -> Vec<u8> {
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+        self.context
+            .bind_framebuffer(GL::FRAMEBUFFER, self.framebuffer.as_ref());
+        self.context
+            .read_pixels_with_opt_u8_array(
+                x,
+                y,
+                width,
+                height,
+                GL::RGBA,
+                GL::UNSIGNED_BYTE,
+                Some(&mut pixels),
+            )
+            .expect("failed to read texture");
+        pixels
+    }
+
     pub fn copy_from(&self, other: &BufferedTexture) -> Result<(), &str> {
         if self.width != other.width || self.height != other.height {
             return Err("mismatched sizes");
@@ -269,6 +363,11 @@ impl SwappableTexture {
     pub fn swap(&mut self) {
         self.parity = !self.parity;
     }
+
+    pub fn set_parameters(&self, tex_params: &[(u32, u32)]) {
+        self.first.set_parameters(tex_params);
+        self.second.set_parameters(tex_params);
+    }
 }
 
 pub fn compile_shader(
@@ -312,6 +411,10 @@ impl Program {
 
         context.attach_shader(&program, vert_shader);
         context.attach_shader(&program, frag_shader);
+        // pin the attribute names Quad and Mesh feed so every program
+        // agrees on their locations, unused names are ignored
+        context.bind_attrib_location(&program, POSITION_ATTRIBUTE, "a_position");
+        context.bind_attrib_location(&program, INSTANCE_ATTRIBUTE, "a_instance");
         context.link_program(&program);
 
         context
@@ -378,35 +481,171 @@ impl Quad {
             .bind_buffer(GL::ARRAY_BUFFER, self.buff.as_ref());
 
         self.context
-            .vertex_attrib_pointer_with_i32(0, 2, GL::FLOAT, false, 0, 0);
-        self.context.enable_vertex_attrib_array(0);
+            .vertex_attrib_pointer_with_i32(POSITION_ATTRIBUTE, 2, GL::FLOAT, false, 0, 0);
+        self.context.enable_vertex_attrib_array(POSITION_ATTRIBUTE);
+
+        bind_target(&self.context, target);
+
+        self.context.clear_color(0.0, 0.0, 0.0, 1.0);
+        self.context.clear(GL::COLOR_BUFFER_BIT);
+
+        self.context.draw_arrays(GL::TRIANGLES, 0, 6);
+    }
+}
+
+impl Drop for Quad {
+    fn drop(&mut self) {
+        self.context.delete_buffer(self.buff.as_ref());
+    }
+}
 
-        match target {
-            Some(tex) => {
-                self.context.viewport(0, 0, tex.width, tex.height);
+fn bind_target(context: &WebGl2RenderingContext, target: Option<&BufferedTexture>) {
+    match target {
+        Some(tex) => {
+            context.viewport(0, 0, tex.width, tex.height);
+            context.bind_framebuffer(GL::FRAMEBUFFER, tex.framebuffer.as_ref());
+        }
+        None => {
+            context.viewport(
+                0,
+                0,
+                context.drawing_buffer_width(),
+                context.drawing_buffer_height(),
+            );
+            context.bind_framebuffer(GL::FRAMEBUFFER, None);
+        }
+    }
+}
+
+fn make_buffer(context: &WebGl2RenderingContext, data: &[f32]) -> Option<WebGlBuffer> {
+    let buff = context.create_buffer();
+    context.bind_buffer(GL::ARRAY_BUFFER, buff.as_ref());
+    unsafe {
+        // same rules as ArrayView, no allocating while the view is alive
+        let view = js_sys::Float32Array::view(data);
+        context.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &view, GL::STATIC_DRAW);
+    }
+    buff
+}
+
+struct Instances {
+    buff: Option<WebGlBuffer>,
+    size: i32,
+    count: i32,
+}
+
+// Arbitrary geometry drawn on top of whatever is already in the target,
+// unlike Quad::blit nothing is cleared and the result is alpha blended.
+// Vertices feed a_position, and if there are instances each instance's
+// values feed a_instance
+pub struct Mesh {
+    context: WebGl2RenderingContext,
+    mode: u32,
+    buff: Option<WebGlBuffer>,
+    vertex_size: i32,
+    vertex_count: i32,
+    instances: Option<Instances>,
+}
+
+impl Mesh {
+    pub fn create(
+        context: &WebGl2RenderingContext,
+        mode: u32,
+        vertex_size: i32,
+        vertices: &[f32],
+    ) -> Self {
+        Mesh {
+            context: context.clone(),
+            mode,
+            buff: make_buffer(context, vertices),
+            vertex_size,
+            vertex_count: vertices.len() as i32 / vertex_size,
+            instances: None,
+        }
+    }
+
+    pub fn create_instanced(
+        context: &WebGl2RenderingContext,
+        mode: u32,
+        vertex_size: i32,
+        vertices: &[f32],
+        instance_size: i32,
+        instances: &[f32],
+    ) -> Self {
+        let mut mesh = Self::create(context, mode, vertex_size, vertices);
+        mesh.instances = Some(Instances {
+            buff: make_buffer(context, instances),
+            size: instance_size,
+            count: instances.len() as i32 / instance_size,
+        });
+        mesh
+    }
+
+    pub fn draw(&self, target: Option<&BufferedTexture>) {
+        self.context
+            .bind_buffer(GL::ARRAY_BUFFER, self.buff.as_ref());
+        self.context.vertex_attrib_pointer_with_i32(
+            POSITION_ATTRIBUTE,
+            self.vertex_size,
+            GL::FLOAT,
+            false,
+            0,
+            0,
+        );
+        self.context.enable_vertex_attrib_array(POSITION_ATTRIBUTE);
+
+        bind_target(&self.context, target);
+
+        // keep the target's own alpha so the canvas stays opaque
+        self.context.enable(GL::BLEND);
+        self.context.blend_func_separate(
+            GL::SRC_ALPHA,
+            GL::ONE_MINUS_SRC_ALPHA,
+            GL::ONE,
+            GL::ONE_MINUS_SRC_ALPHA,
+        );
+
+        match &self.instances {
+            Some(instances) => {
                 self.context
-                    .bind_framebuffer(GL::FRAMEBUFFER, tex.framebuffer.as_ref());
-            }
-            None => {
-                self.context.viewport(
+                    .bind_buffer(GL::ARRAY_BUFFER, instances.buff.as_ref());
+                self.context.vertex_attrib_pointer_with_i32(
+                    INSTANCE_ATTRIBUTE,
+                    instances.size,
+                    GL::FLOAT,
+                    false,
                     0,
                     0,
-                    self.context.drawing_buffer_width(),
-                    self.context.drawing_buffer_height(),
                 );
-                self.context.bind_framebuffer(GL::FRAMEBUFFER, None);
+                self.context.enable_vertex_attrib_array(INSTANCE_ATTRIBUTE);
+                self.context.vertex_attrib_divisor(INSTANCE_ATTRIBUTE, 1);
+
+                self.context.draw_arrays_instanced(
+                    self.mode,
+                    0,
+                    self.vertex_count,
+                    instances.count,
+                );
+
+                // leave things how Quad expects them
+                self.context.vertex_attrib_divisor(INSTANCE_ATTRIBUTE, 0);
+                self.context.disable_vertex_attrib_array(INSTANCE_ATTRIBUTE);
+            }
+            None => {
+                self.context
+                    .draw_arrays(self.mode, 0, self.vertex_count);
             }
         }
 
-        self.context.clear_color(0.0, 0.0, 0.0, 1.0);
-        self.context.clear(GL::COLOR_BUFFER_BIT);
-
-        self.context.draw_arrays(GL::TRIANGLES, 0, 6);
+        self.context.disable(GL::BLEND);
     }
 }
 
-impl Drop for Quad {
+impl Drop for Mesh {
     fn drop(&mut self) {
         self.context.delete_buffer(self.buff.as_ref());
+        if let Some(instances) = &self.instances {
+            self.context.delete_buffer(instances.buff.as_ref());
+        }
     }
 }
@@ -0,0 +1,237 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::Closure;
+use web_sys::window;
+
+pub struct FixedTimestep {
+    step: f64,
+    max_steps: u32,
+    accumulated: f64,
+}
+
+impl FixedTimestep {
+    pub fn new(step: f64, max_steps: u32) -> Self {
+        FixedTimestep {
+            step,
+            max_steps,
+            accumulated: 0.0,
+        }
+    }
+
+    pub fn step(&self) -> f64 {
+        self.step
+    }
+
+    // adds the elapsed time and returns how many steps to take to catch up,
+    // if we're too far behind the backlog is dropped instead of letting
+    // every following frame take max_steps
+    pub fn advance(&mut self, elapsed: f64) -> u32 {
+        self.accumulated += elapsed.max(0.0);
+        let steps = (self.accumulated / self.step).floor();
+        if steps >= self.max_steps as f64 {
+            self.accumulated %= self.step;
+            return self.max_steps;
+        }
+        self.accumulated -= steps * self.step;
+        steps as u32
+    }
+
+    // how far we are between the last step and the next one, in [0, 1)
+    pub fn alpha(&self) -> f32 {
+        (self.accumulated / self.step) as f32
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Frame {
+    // the requestAnimationFrame timestamp, in milliseconds
+    pub time: f64,
+    // milliseconds since the previous frame, zero for the first frame
+    pub dt: f64,
+    pub count: u64,
+}
+
+struct LoopState {
+    tick: Option<Closure<dyn FnMut(f64)>>,
+    handle: Option<i32>,
+    paused: bool,
+    // set once the loop is dropped, a frame that's running then mustn't
+    // request another
+    stopped: bool,
+    prev_time: Option<f64>,
+    count: u64,
+}
+
+impl LoopState {
+    fn request(&mut self) {
+        if self.handle.is_some() || self.paused || self.stopped {
+            return;
+        }
+        let tick = self.tick.as_ref().unwrap().as_ref().unchecked_ref();
+        self.handle = Some(
+            window()
+                .expect("no window")
+                .request_animation_frame(tick)
+                .expect("requestAnimationFrame failed"),
+        );
+    }
+
+    fn cancel(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = window().expect("no window").cancel_animation_frame(handle);
+        }
+    }
+}
+
+// Owns a requestAnimationFrame callback that keeps re-requesting itself
+// until the loop is dropped, which cancels the frame waiting to run
+pub struct AnimationLoop {
+    state: Rc<RefCell<LoopState>>,
+}
+
+impl AnimationLoop {
+    pub fn start(mut callback: impl FnMut(Frame) + 'static) -> Self {
+        let state = Rc::new(RefCell::new(LoopState {
+            tick: None,
+            handle: None,
+            paused: false,
+            stopped: false,
+            prev_time: None,
+            count: 0,
+        }));
+
+        // the callback only holds a weak reference so dropping the
+        // loop frees it rather than leaking the cycle
+        let weak = Rc::downgrade(&state);
+        let tick = Closure::new(move |time: f64| {
+            let Some(state) = weak.upgrade() else {
+                return;
+            };
+            // the state isn't borrowed while the callback runs, so it's free
+            // to do anything including stopping the loop
+            let frame = {
+                let mut state = state.borrow_mut();
+                state.handle = None;
+                let frame = Frame {
+                    time,
+                    dt: state.prev_time.map_or(0.0, |prev| time - prev),
+                    count: state.count,
+                };
+                state.prev_time = Some(time);
+                state.count += 1;
+                frame
+            };
+            callback(frame);
+            state.borrow_mut().request();
+        });
+        state.borrow_mut().tick = Some(tick);
+        state.borrow_mut().request();
+
+        AnimationLoop { state }
+    }
+
+    // no frames run until resume
+    pub fn pause(&self) {
+        let mut state = self.state.borrow_mut();
+        state.paused = true;
+        state.cancel();
+    }
+
+    // the first frame after a pause has a dt of zero rather than the whole
+    // time spent paused
+    pub fn resume(&self) {
+        let mut state = self.state.borrow_mut();
+        if !state.paused {
+            return;
+        }
+        state.paused = false;
+        state.prev_time = None;
+        state.request();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.borrow().paused
+    }
+}
+
+impl Drop for AnimationLoop {
+    fn drop(&mut self) {
+        let mut state = self.state.borrow_mut();
+        state.stopped = true;
+        state.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulates_partial_steps() {
+        let mut ticks = FixedTimestep::new(10.0, 4);
+        assert_eq!(ticks.advance(4.0), 0);
+        assert_eq!(ticks.advance(4.0), 0);
+        assert_eq!(ticks.advance(4.0), 1);
+        assert_eq!(ticks.advance(25.0), 2);
+        // negative time, a clock going backwards, counts as none
+        assert_eq!(ticks.advance(-100.0), 0);
+        assert_eq!(ticks.advance(3.0), 1);
+    }
+
+    #[test]
+    fn clamps_to_max_steps() {
+        let mut ticks = FixedTimestep::new(10.0, 4);
+        assert_eq!(ticks.advance(40.0), 4);
+        assert_eq!(ticks.advance(1000.0), 4);
+        assert_eq!(ticks.advance(0.0), 0);
+    }
+
+    #[test]
+    fn alpha_stays_in_range() {
+        let mut ticks = FixedTimestep::new(10.0, 4);
+        assert_eq!(ticks.alpha(), 0.0);
+        for elapsed in [0.0, 3.0, 7.5, 9.99, 10.0, 33.3, 1000.0, 16.7] {
+            ticks.advance(elapsed);
+            assert!((0.0..1.0).contains(&ticks.alpha()), "{}", ticks.alpha());
+        }
+    }
+
+    #[test]
+    fn catches_up_after_a_long_frame() {
+        // a 60Hz step with a frame three and a half steps long
+        let step = 1000.0 / 60.0;
+        let mut ticks = FixedTimestep::new(step, 8);
+        assert_eq!(ticks.advance(step), 1);
+        assert_eq!(ticks.advance(step * 3.5), 3);
+        // then back to one a frame, with the half step still banked
+        assert_eq!(ticks.advance(step), 1);
+        assert_eq!(ticks.advance(step * 0.5), 1);
+    }
+
+    #[test]
+    fn clamp_drops_the_backlog() {
+        let mut ticks = FixedTimestep::new(10.0, 4);
+        // a tab coming back after ten seconds away
+        assert_eq!(ticks.advance(10_005.0), 4);
+        // only the part of a step left over is kept, the next frame doesn't
+        // have to catch up on the rest
+        assert_eq!(ticks.advance(4.0), 0);
+        assert_eq!(ticks.advance(1.0), 1);
+    }
+
+    #[test]
+    fn leftover_time_goes_into_alpha() {
+        let mut ticks = FixedTimestep::new(10.0, 4);
+        ticks.advance(25.0);
+        assert_eq!(ticks.alpha(), 0.5);
+        ticks.advance(2.5);
+        assert_eq!(ticks.alpha(), 0.75);
+        assert_eq!(ticks.advance(2.5), 1);
+        assert_eq!(ticks.alpha(), 0.0);
+        // and survives the clamp
+        ticks.advance(1002.0);
+        assert!((ticks.alpha() - 0.2).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,284 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use leptos::prelude::*;
+use leptos::wasm_bindgen::JsCast;
+
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url, WebGl2RenderingContext, console};
+
+type GL = WebGl2RenderingContext;
+
+const DEFAULT_FRAMES: u32 = 120;
+
+// Snapshot and recording requests shared between a demo's controls and the
+// Recorder in its animation loop
+#[derive(Clone, Copy)]
+pub struct Capture {
+    pending_snapshots: RwSignal<u32>,
+    frames: RwSignal<u32>,
+    // frames recorded so far while a recording is running
+    recorded: RwSignal<Option<u32>>,
+}
+
+impl Capture {
+    pub fn new() -> Self {
+        Capture {
+            pending_snapshots: RwSignal::new(0),
+            frames: RwSignal::new(DEFAULT_FRAMES),
+            recorded: RwSignal::new(None),
+        }
+    }
+
+    // while recording the loop should take exactly one step per frame so
+    // every recorded frame covers the same simulated time
+    pub fn is_recording(&self) -> bool {
+        self.recorded.with_untracked(Option::is_some)
+    }
+
+    fn take_snapshot(&self) -> bool {
+        if self.pending_snapshots.get_untracked() == 0 {
+            return false;
+        }
+        self.pending_snapshots
+            .update_untracked(|snapshots| *snapshots -= 1);
+        true
+    }
+}
+
+#[component]
+pub fn CaptureControls(capture: Capture) -> impl IntoView {
+    view! {
+        <div style:margin="40px">
+            <button on:click=move |_| capture.pending_snapshots.update(|snapshots| *snapshots += 1)>
+                "Snapshot"
+            </button>
+            <button
+                disabled=move || capture.recorded.get().is_some()
+                on:click=move |_| capture.recorded.set(Some(0))
+            >
+                {move || match capture.recorded.get() {
+                    Some(recorded) => format!("Recording {recorded}/{}", capture.frames.get()),
+                    None => "Record".to_string(),
+                }}
+            </button>
+            <label>
+                " Frames "
+                <input
+                    type="number"
+                    min="1"
+                    max="600"
+                    disabled=move || capture.recorded.get().is_some()
+                    prop:value=move || capture.frames.get().to_string()
+                    on:change=move |ev| {
+                        if let Ok(frames) = event_target_value(&ev).parse::<u32>() {
+                            capture.frames.set(frames.clamp(1, 600));
+                        }
+                    }
+                />
+            </label>
+        </div>
+    }
+}
+
+// lets the png writer own something we can still take the bytes back out of
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct Recording {
+    buffer: SharedBuffer,
+    writer: png::Writer<SharedBuffer>,
+    frames: u32,
+}
+
+// Lives in the animation loop and saves what was just drawn to the canvas,
+// a single PNG for snapshots or an animated PNG for recordings
+pub struct Recorder {
+    capture: Capture,
+    name: &'static str,
+    recording: Option<Recording>,
+}
+
+impl Recorder {
+    pub fn new(capture: Capture, name: &'static str) -> Self {
+        Recorder {
+            capture,
+            name,
+            recording: None,
+        }
+    }
+
+    // call once the frame has been drawn to the canvas, step_ms is the
+    // simulated time between recorded frames
+    pub fn capture(&mut self, context: &WebGl2RenderingContext, step_ms: f64) {
+        let snapshot = self.capture.take_snapshot();
+        let recording = self.capture.is_recording();
+        if !snapshot && !recording {
+            return;
+        }
+
+        let width = context.drawing_buffer_width() as u32;
+        let height = context.drawing_buffer_height() as u32;
+        let pixels = read_canvas(context, width, height);
+
+        if snapshot {
+            match encode_png(width, height, &pixels) {
+                Ok(bytes) => download(&bytes, &format!("{}.png", self.name), "image/png"),
+                Err(err) => console::log_1(&format!("Failed to encode snapshot: {err}").into()),
+            }
+        }
+
+        if recording && let Err(err) = self.record(width, height, &pixels, step_ms) {
+            console::log_1(&format!("Failed to record: {err}").into());
+            self.recording = None;
+            self.capture.recorded.set(None);
+        }
+    }
+
+    fn record(
+        &mut self,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+        step_ms: f64,
+    ) -> Result<(), png::EncodingError> {
+        if self.recording.is_none() {
+            let frames = self.capture.frames.get_untracked();
+            let buffer = SharedBuffer::default();
+            let mut encoder = png::Encoder::new(buffer.clone(), width, height);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_animated(frames, 0)?;
+            encoder.set_frame_delay(step_ms.round() as u16, 1000)?;
+            self.recording = Some(Recording {
+                buffer,
+                writer: encoder.write_header()?,
+                frames,
+            });
+        }
+
+        let Some(recording) = self.recording.as_mut() else {
+            return Ok(());
+        };
+        recording.writer.write_image_data(pixels)?;
+        let recorded = self.capture.recorded.get_untracked().unwrap_or(0) + 1;
+
+        if recorded < recording.frames {
+            self.capture.recorded.set(Some(recorded));
+            return Ok(());
+        }
+
+        let Recording { buffer, writer, .. } = self.recording.take().unwrap();
+        writer.finish()?;
+        self.capture.recorded.set(None);
+        download(
+            &buffer.0.borrow(),
+            &format!("{}-recording.png", self.name),
+            "image/png",
+        );
+        Ok(())
+    }
+}
+
+// RGBA bytes of the default framebuffer with the top row first
+fn read_canvas(context: &WebGl2RenderingContext, width: u32, height: u32) -> Vec<u8> {
+    let mut pixels = vec![0u8; (width * height * 4) as usize];
+    context.bind_framebuffer(GL::FRAMEBUFFER, None);
+    context
+        .read_pixels_with_opt_u8_array(
+            0,
+            0,
+            width as i32,
+            height as i32,
+            GL::RGBA,
+            GL::UNSIGNED_BYTE,
+            Some(&mut pixels),
+        )
+        .expect("failed to read canvas");
+    flip_rows(&pixels, (width * 4) as usize)
+}
+
+fn flip_rows(pixels: &[u8], row_len: usize) -> Vec<u8> {
+    pixels
+        .chunks_exact(row_len)
+        .rev()
+        .flatten()
+        .copied()
+        .collect()
+}
+
+fn encode_png(width: u32, height: u32, pixels: &[u8]) -> Result<Vec<u8>, png::EncodingError> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(pixels)?;
+    writer.finish()?;
+    Ok(bytes)
+}
+
+// hands bytes to the browser as a file download
+pub fn download(bytes: &[u8], name: &str, mime: &str) {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let options = BlobPropertyBag::new();
+    options.set_type(mime);
+    let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options)
+        .expect("failed to make blob");
+    let url = Url::create_object_url_with_blob(&blob).expect("failed to make url");
+    let anchor = document()
+        .create_element("a")
+        .expect("failed to make anchor")
+        .unchecked_into::<HtmlAnchorElement>();
+    anchor.set_href(&url);
+    anchor.set_download(name);
+    anchor.click();
+    Url::revoke_object_url(&url).expect("failed to revoke url");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a distinct RGBA pixel for every cell, numbered along each row
+    fn frame(width: u8, height: u8) -> Vec<u8> {
+        (0..width * height)
+            .flat_map(|idx| [idx, idx * 10, 255 - idx, 255])
+            .collect()
+    }
+
+    #[test]
+    fn flips_rows() {
+        // 2 wide and 3 tall
+        let pixels = frame(2, 3);
+        let flipped = flip_rows(&pixels, 2 * 4);
+        assert_eq!(&flipped[..8], &pixels[16..]);
+        assert_eq!(&flipped[8..16], &pixels[8..16]);
+        assert_eq!(&flipped[16..], &pixels[..8]);
+        assert_eq!(flip_rows(&flipped, 2 * 4), pixels);
+    }
+
+    #[test]
+    fn png_decodes_back() {
+        let pixels = frame(3, 2);
+        let bytes = encode_png(3, 2, &pixels).unwrap();
+        let decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+        let mut reader = decoder.read_info().unwrap();
+        let mut decoded = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut decoded).unwrap();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+        assert_eq!(&decoded[..4], &pixels[..4]);
+        assert_eq!(&decoded[..info.buffer_size()], pixels);
+    }
+}
@@ -0,0 +1,47 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{File, console};
+
+pub trait SelectOption: Copy + PartialEq + Send + Sync + 'static {
+    const ALL: &'static [Self];
+
+    fn label(&self) -> &'static str;
+}
+
+// reads a picked or dropped file into target once the browser has it
+pub fn read_file(file: File, target: RwSignal<Option<Vec<u8>>>) {
+    spawn_local(async move {
+        match JsFuture::from(file.array_buffer()).await {
+            Ok(buffer) => target.set(Some(js_sys::Uint8Array::new(&buffer).to_vec())),
+            Err(err) => console::log_2(&"Failed to read file".into(), &err),
+        }
+    });
+}
+
+#[component]
+pub fn EnumSelect<T: SelectOption>(value: RwSignal<T>) -> impl IntoView {
+    let options = T::ALL
+        .iter()
+        .enumerate()
+        .map(|(idx, option)| {
+            view! {
+                <option value=idx.to_string() selected=move || value.get() == *option>
+                    {option.label()}
+                </option>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <select on:change=move |ev| {
+            if let Some(option) = event_target_value(&ev)
+                .parse::<usize>()
+                .ok()
+                .and_then(|idx| T::ALL.get(idx))
+            {
+                value.set(*option);
+            }
+        }>{options}</select>
+    }
+}
@@ -1,62 +1,159 @@
+mod advection;
+mod boundary;
+mod display;
+mod dye;
+mod particles;
+mod picture;
+mod smoke;
+
 use utility::prelude::*;
 
-use std::cell::RefCell;
 use std::cmp;
+use std::convert::Infallible;
 use std::ops::Div;
-use std::ops::Sub;
-use std::rc::Rc;
 
 use leptos::html::Canvas;
 use leptos::prelude::*;
 
-use leptos::wasm_bindgen::prelude::*;
-
 use leptos::wasm_bindgen::JsCast;
-use leptos_use::UseMouseEventExtractor;
-use leptos_use::UseMouseReturn;
-use web_sys::HtmlElement;
-use web_sys::MouseEvent;
-use web_sys::Touch;
-use web_sys::WebGl2RenderingContext;
+use leptos_use::use_event_listener;
+use serde::{Deserialize, Serialize};
 use web_sys::console;
+use web_sys::{HtmlInputElement, WebGl2RenderingContext};
 
-use leptos_use::{UseMouseCoordType, UseMouseOptions, use_mouse_with_options};
+use crate::capture::{Capture, CaptureControls, Recorder};
+use crate::controls::{EnumSelect, SelectOption, read_file};
+use crate::pointers::{PointerQueue, Pointers, use_pointers};
+use crate::replay::{InputLog, Replay, ReplayControls};
+use crate::script::{self, PointerScript};
+use crate::snapshot::{
+    DemoKind, Snapshot, SnapshotControls, Snapshots, TextureData, TextureFormat,
+};
+use crate::transport::{SimClock, Transport, own_loop};
+use advection::{Advection, AdvectionMode};
+use boundary::BoundaryMode;
+use display::{Colormap, DisplayMode, Overlay};
+use dye::{DyeColorMode, DyeResolution};
+use particles::{ParticleCount, Particles};
+use picture::Picture;
+use smoke::SimMode;
 
 type GL = WebGl2RenderingContext;
-#[derive(Clone)]
-struct OffsetExtractor;
-impl UseMouseEventExtractor for OffsetExtractor {
-    fn extract_mouse_coords(&self, event: &MouseEvent) -> Option<(f64, f64)> {
-        match event.buttons() % 2 {
-            1 => Some((event.offset_x() as f64, event.offset_y() as f64)),
-            _ => None,
+
+// must match MAX_IMPULSES in force.frag and splat.frag
+const MAX_IMPULSES: usize = 16;
+
+// the sim always advances in steps of one timestep unit, each standing in
+// for this many milliseconds of real time
+const STEP_MS: f64 = 1000.0 / 60.0;
+
+// cap on the steps taken in one frame so a slow frame can't snowball
+const MAX_STEPS_PER_FRAME: u32 = 4;
+
+// frames between reading back the displayed field to rescale its colours
+const RANGE_INTERVAL: u64 = 15;
+
+// steps taken every frame in deterministic mode, whatever the frame rate
+const DETERMINISTIC_STEPS: u32 = 1;
+
+// arrows and streamlines start from the centres of an N x N lattice
+const OVERLAY_LATTICE: usize = 32;
+
+// must be at most MAX_STEPS in streamline.vert
+const STREAMLINE_VERTICES: usize = 24;
+
+// tex_bilerp, shared by every shader that samples a float texture between texels
+const BILERP: &str = include_str!("shaders/bilerp.glsl");
+
+#[derive(Clone, Copy)]
+struct FluidSettings {
+    dye_mode: RwSignal<DyeColorMode>,
+    dye_color: RwSignal<String>,
+    dye_dissipation: RwSignal<f32>,
+    dye_resolution: RwSignal<DyeResolution>,
+    display_mode: RwSignal<DisplayMode>,
+    colormap: RwSignal<Colormap>,
+    overlay: RwSignal<Overlay>,
+    particle_count: RwSignal<ParticleCount>,
+    advection: RwSignal<AdvectionMode>,
+    sim_mode: RwSignal<SimMode>,
+    boundary: RwSignal<BoundaryMode>,
+    ambient_temperature: RwSignal<f32>,
+    // bytes of a picked or dropped image waiting for the loop to load them
+    picture: RwSignal<Option<Vec<u8>>>,
+    picture_obstacles: RwSignal<bool>,
+    // replaces live input with the pointer script, as JSON, and runs
+    // a fixed number of steps per frame
+    deterministic: RwSignal<bool>,
+    script: RwSignal<String>,
+}
+
+impl Default for FluidSettings {
+    fn default() -> Self {
+        FluidSettings {
+            dye_mode: RwSignal::new(DyeColorMode::Rainbow),
+            dye_color: RwSignal::new("#ff6a00".to_string()),
+            dye_dissipation: RwSignal::new(0.002),
+            dye_resolution: RwSignal::new(DyeResolution::Medium),
+            display_mode: RwSignal::new(DisplayMode::Dye),
+            colormap: RwSignal::new(Colormap::Diverging),
+            overlay: RwSignal::new(Overlay::None),
+            particle_count: RwSignal::new(ParticleCount::Off),
+            advection: RwSignal::new(AdvectionMode::SemiLagrangian),
+            sim_mode: RwSignal::new(SimMode::Fluid),
+            boundary: RwSignal::new(BoundaryMode::Walls),
+            ambient_temperature: RwSignal::new(0.0),
+            picture: RwSignal::new(None),
+            picture_obstacles: RwSignal::new(false),
+            deterministic: RwSignal::new(false),
+            script: RwSignal::new(
+                serde_json::to_string(&script::figure_eight(4000.0, 50.0))
+                    .expect("failed to write default script"),
+            ),
         }
     }
+}
+
+// settings saved alongside the textures in a snapshot
+#[derive(Serialize, Deserialize)]
+struct FluidConfig {
+    sim_mode: SimMode,
+    advection: AdvectionMode,
+    boundary: BoundaryMode,
+    dye_dissipation: f32,
+    ambient_temperature: f32,
+}
 
-    fn extract_touch_coords(&self, touch: &Touch) -> Option<(f64, f64)> {
-        let element = touch
-            .target()
-            .unwrap()
-            .dyn_ref::<HtmlElement>()
-            .unwrap()
-            .clone();
-        let rect = element.get_bounding_client_rect();
-        Some((
-            touch.client_x() as f64 - rect.x(),
-            touch.client_y() as f64 - rect.y(),
-        ))
+impl FluidConfig {
+    fn apply(&self, settings: FluidSettings) {
+        settings.sim_mode.set(self.sim_mode);
+        settings.advection.set(self.advection);
+        settings.boundary.set(self.boundary);
+        settings.dye_dissipation.set(self.dye_dissipation);
+        settings.ambient_temperature.set(self.ambient_temperature);
     }
 }
 
 #[component]
-pub fn App() -> impl IntoView {
+pub fn App(snapshots: Snapshots) -> impl IntoView {
     let canvas_ref = NodeRef::<Canvas>::new();
-    let mouse = use_mouse_with_options(
-        UseMouseOptions::default()
-            .target(canvas_ref)
-            .coord_type(UseMouseCoordType::Custom(OffsetExtractor)),
-    );
-    let mouse_rc = Rc::new(mouse);
+    let pointer_queue = use_pointers(canvas_ref);
+    let settings = FluidSettings::default();
+    let clock = SimClock::new();
+    let capture = Capture::new();
+    let replay = Replay::new();
+    // dropping an image on the canvas loads it like picking one does
+    let _ = use_event_listener(canvas_ref, leptos::ev::dragover, |ev| ev.prevent_default());
+    let _ = use_event_listener(canvas_ref, leptos::ev::drop, move |ev| {
+        ev.prevent_default();
+        if let Some(file) = ev
+            .data_transfer()
+            .and_then(|transfer| transfer.files())
+            .and_then(|files| files.get(0))
+        {
+            read_file(file, settings.picture);
+        }
+    });
     Effect::new(move |_| {
         if let Some(canvas) = canvas_ref.get() {
             canvas.set_width(1024);
@@ -67,14 +164,116 @@ pub fn App() -> impl IntoView {
                 .expect("object")
                 .dyn_into::<WebGl2RenderingContext>()
                 .unwrap();
-            canvas_fill(context, mouse_rc.clone());
+            canvas_fill(
+                context,
+                pointer_queue.clone(),
+                settings,
+                clock,
+                capture,
+                replay,
+                snapshots,
+            );
         }
         console::log_1(&"Running Main Effect".into());
     });
 
     view! {
         <h1 style:margin="40px">"WebGl Fluid Sim"</h1>
-        <canvas style:padding="0px" style:touch-action="pinch-zoom" node_ref=canvas_ref />
+        <canvas style:padding="0px" style:touch-action="none" node_ref=canvas_ref />
+        <Transport clock=clock />
+        <CaptureControls capture=capture />
+        <ReplayControls replay=replay name="fluids" />
+        <SnapshotControls snapshots=snapshots />
+        <div style:margin="40px">
+            <label>"Dye colour " <EnumSelect value=settings.dye_mode /></label>
+            <input
+                type="color"
+                prop:value=move || settings.dye_color.get()
+                on:input=move |ev| settings.dye_color.set(event_target_value(&ev))
+            />
+            <label>
+                " Dissipation "
+                <input
+                    type="range"
+                    min="0"
+                    max="0.02"
+                    step="0.001"
+                    prop:value=move || settings.dye_dissipation.get().to_string()
+                    on:input=move |ev| {
+                        if let Ok(value) = event_target_value(&ev).parse() {
+                            settings.dye_dissipation.set(value);
+                        }
+                    }
+                />
+            </label>
+            <label>" Resolution " <EnumSelect value=settings.dye_resolution /></label>
+        </div>
+        <div style:margin="40px">
+            <label>
+                "Image "
+                <input
+                    type="file"
+                    accept="image/png,image/jpeg"
+                    on:change=move |ev| {
+                        let input = event_target::<HtmlInputElement>(&ev);
+                        if let Some(file) = input.files().and_then(|files| files.get(0)) {
+                            read_file(file, settings.picture);
+                        }
+                    }
+                />
+            </label>
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=move || settings.picture_obstacles.get()
+                    on:change=move |ev| settings.picture_obstacles.set(event_target_checked(&ev))
+                />
+                " Transparent parts are obstacles"
+            </label>
+        </div>
+        <div style:margin="40px">
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=move || settings.deterministic.get()
+                    on:change=move |ev| settings.deterministic.set(event_target_checked(&ev))
+                />
+                " Deterministic, restarts and plays this pointer script"
+            </label>
+            <br />
+            <textarea
+                rows="4"
+                cols="80"
+                prop:value=move || settings.script.get()
+                on:change=move |ev| settings.script.set(event_target_value(&ev))
+            />
+        </div>
+        <div style:margin="40px">
+            <label>"Simulate " <EnumSelect value=settings.sim_mode /></label>
+            <label>" Advection " <EnumSelect value=settings.advection /></label>
+            <label>" Edges " <EnumSelect value=settings.boundary /></label>
+            <label>
+                " Ambient temperature "
+                <input
+                    type="range"
+                    min="-0.5"
+                    max="0.5"
+                    step="0.05"
+                    prop:value=move || settings.ambient_temperature.get().to_string()
+                    on:input=move |ev| {
+                        if let Ok(value) = event_target_value(&ev).parse() {
+                            settings.ambient_temperature.set(value);
+                        }
+                    }
+                />
+            </label>
+        </div>
+        <div style:margin="40px">
+            <label>"Show " <EnumSelect value=settings.display_mode /></label>
+            <label>" Colour map " <EnumSelect value=settings.colormap /></label>
+            <label>" Overlay " <EnumSelect value=settings.overlay /></label>
+            <label>" Particles " <EnumSelect value=settings.particle_count /></label>
+        </div>
         <h2 style:margin="40px">"Written by Jackson Welles"</h2>
         <h2 style:margin="40px">"Theory and shaders from GPU Gems: Chapter 38."</h2>
         <h2 style:margin="40px">
@@ -83,10 +282,14 @@ pub fn App() -> impl IntoView {
     }
 }
 
-render_pipeline!(AdvectPipeline, "shaders/advect.frag");
-
 render_pipeline!(ImpulsePipeline, "shaders/force.frag");
 
+render_pipeline!(SplatPipeline, "shaders/splat.frag");
+
+render_pipeline!(SourcePipeline, "shaders/source.frag");
+
+render_pipeline!(BuoyancyPipeline, "shaders/buoyancy.frag");
+
 render_pipeline!(DivergencePipeline, "shaders/divergence.frag");
 
 render_pipeline!(JacobiPipeline, "shaders/jacobi.frag");
@@ -95,7 +298,29 @@ render_pipeline!(BoundaryPipeline, "shaders/boundary.frag");
 
 render_pipeline!(GradientSubtractPipeline, "shaders/gradient.frag");
 
-fn canvas_fill(context: WebGl2RenderingContext, mouse: Rc<UseMouseReturn>) {
+render_pipeline!(DyeDisplayPipeline, "shaders/display_dye.frag");
+
+render_pipeline!(CurlPipeline, "shaders/curl.frag");
+
+render_pipeline!(ScalarDisplayPipeline, "shaders/display_scalar.frag");
+
+render_pipeline!(VelocityDisplayPipeline, "shaders/display_velocity.frag");
+
+render_pipeline!(SmokeDisplayPipeline, "shaders/display_smoke.frag");
+
+render_pipeline!(ArrowPipeline, "shaders/arrow.vert");
+
+render_pipeline!(StreamlinePipeline, "shaders/streamline.vert");
+
+fn canvas_fill(
+    context: WebGl2RenderingContext,
+    pointer_queue: PointerQueue,
+    settings: FluidSettings,
+    clock: SimClock,
+    capture: Capture,
+    replay: Replay,
+    snapshots: Snapshots,
+) {
     context.get_extension("EXT_color_buffer_float").unwrap();
     context.get_extension("OES_texture_float_linear").unwrap();
     let quad_vert_shader = compile_shader(
@@ -105,10 +330,10 @@ fn canvas_fill(context: WebGl2RenderingContext, mouse: Rc<UseMouseReturn>) {
     )
     .unwrap();
 
-    let quad_frag_shader = compile_shader(
+    let dye_display_frag_shader = compile_shader(
         &context,
         GL::FRAGMENT_SHADER,
-        include_str!("shaders/quad.frag"),
+        include_str!("shaders/display_dye.frag"),
     )
     .unwrap();
 
@@ -119,10 +344,24 @@ fn canvas_fill(context: WebGl2RenderingContext, mouse: Rc<UseMouseReturn>) {
     )
     .unwrap();
 
-    let advect_frag_shader = compile_shader(
+    let splat_frag_shader = compile_shader(
+        &context,
+        GL::FRAGMENT_SHADER,
+        include_str!("shaders/splat.frag"),
+    )
+    .unwrap();
+
+    let source_frag_shader = compile_shader(
         &context,
         GL::FRAGMENT_SHADER,
-        include_str!("shaders/advect.frag"),
+        include_str!("shaders/source.frag"),
+    )
+    .unwrap();
+
+    let buoyancy_frag_shader = compile_shader(
+        &context,
+        GL::FRAGMENT_SHADER,
+        include_str!("shaders/buoyancy.frag"),
     )
     .unwrap();
 
@@ -154,12 +393,59 @@ fn canvas_fill(context: WebGl2RenderingContext, mouse: Rc<UseMouseReturn>) {
     )
     .unwrap();
 
-    let window_w = context.drawing_buffer_width() as usize;
-    let window_h = context.drawing_buffer_height() as usize;
+    let curl_frag_shader = compile_shader(
+        &context,
+        GL::FRAGMENT_SHADER,
+        include_str!("shaders/curl.frag"),
+    )
+    .unwrap();
+
+    let scalar_display_frag_shader = compile_shader(
+        &context,
+        GL::FRAGMENT_SHADER,
+        include_str!("shaders/display_scalar.frag"),
+    )
+    .unwrap();
+
+    let velocity_display_frag_shader = compile_shader(
+        &context,
+        GL::FRAGMENT_SHADER,
+        include_str!("shaders/display_velocity.frag"),
+    )
+    .unwrap();
+
+    let smoke_display_frag_shader = compile_shader(
+        &context,
+        GL::FRAGMENT_SHADER,
+        &with_bilerp(include_str!("shaders/display_smoke.frag")),
+    )
+    .unwrap();
+
+    let arrow_vert_shader = compile_shader(
+        &context,
+        GL::VERTEX_SHADER,
+        include_str!("shaders/arrow.vert"),
+    )
+    .unwrap();
+
+    let streamline_vert_shader = compile_shader(
+        &context,
+        GL::VERTEX_SHADER,
+        include_str!("shaders/streamline.vert"),
+    )
+    .unwrap();
+
+    let overlay_frag_shader = compile_shader(
+        &context,
+        GL::FRAGMENT_SHADER,
+        include_str!("shaders/overlay.frag"),
+    )
+    .unwrap();
+
     let sim_w = 128;
     let sim_h = 128;
-    let dye_w = window_w;
-    let dye_h = window_h;
+    let mut dye_resolution = settings.dye_resolution.get_untracked();
+    let dye_side = dye_resolution as usize;
 
     let force_radius = 1.0 / 24.0;
     let force_scale = 7.0;
@@ -183,217 +469,721 @@ fn canvas_fill(context: WebGl2RenderingContext, mouse: Rc<UseMouseReturn>) {
     let pressure_beta = (0.25f32, 0.25f32);
 
     let force_program = Program::create(&context, &quad_vert_shader, &force_frag_shader);
-    let quad_program = Program::create(&context, &quad_vert_shader, &quad_frag_shader);
-    let advect_program = Program::create(&context, &quad_vert_shader, &advect_frag_shader);
+    let splat_program = Program::create(&context, &quad_vert_shader, &splat_frag_shader);
+    let source_program = Program::create(&context, &quad_vert_shader, &source_frag_shader);
+    let buoyancy_program = Program::create(&context, &quad_vert_shader, &buoyancy_frag_shader);
+    let dye_display_program =
+        Program::create(&context, &quad_vert_shader, &dye_display_frag_shader);
+    let curl_program = Program::create(&context, &quad_vert_shader, &curl_frag_shader);
+    let scalar_display_program =
+        Program::create(&context, &quad_vert_shader, &scalar_display_frag_shader);
+    let velocity_display_program =
+        Program::create(&context, &quad_vert_shader, &velocity_display_frag_shader);
+    let smoke_display_program =
+        Program::create(&context, &quad_vert_shader, &smoke_display_frag_shader);
+    let arrow_program = Program::create(&context, &arrow_vert_shader, &overlay_frag_shader);
+    let streamline_program =
+        Program::create(&context, &streamline_vert_shader, &overlay_frag_shader);
     let boundary_program = Program::create(&context, &quad_vert_shader, &boundary_frag_shader);
     let divergence_program = Program::create(&context, &quad_vert_shader, &divergence_frag_shader);
     let gradient_program = Program::create(&context, &quad_vert_shader, &gradient_frag_shader);
     let jacobi_program = Program::create(&context, &quad_vert_shader, &jacobi_frag_shader);
 
-    let boundary_texture = make_boundary_offsets(sim_w, sim_h, &context);
+    let mut boundary_mode = BoundaryMode::Walls;
+    let mut obstacles: Option<Vec<bool>> = None;
+    let boundary_texture = make_boundary_offsets(boundary_mode, sim_w, sim_h, &context);
     let temp_texture = make_blank::<BufferedTexture>(sim_w, sim_h, &context);
     let blank_texture = make_blank::<BufferedTexture>(sim_w, sim_h, &context);
+    let field_texture = make_blank::<BufferedTexture>(sim_w, sim_h, &context);
     let mut pressure_texture = make_blank::<SwappableTexture>(sim_w, sim_h, &context);
     let mut velocity_texture = make_blank::<SwappableTexture>(sim_w, sim_h, &context);
-    let mut dye_texture = make_initial_dye(dye_w, dye_h, &context);
+    let mut dye_texture = make_initial_dye::<SwappableTexture>(dye_side, dye_side, &context);
+    let mut previous_dye = make_initial_dye::<BufferedTexture>(dye_side, dye_side, &context);
+    let impulse_texture = make_impulses(&context);
+    // temperature in red and smoke density in green
+    let mut smoke_texture = make_blank::<SwappableTexture>(sim_w, sim_h, &context);
+    let velocity_scratch = make_blank::<SwappableTexture>(sim_w, sim_h, &context);
+    // only made while MacCormack is on, at 2048² it's a lot to keep around
+    let mut dye_scratch: Option<SwappableTexture> = None;
 
-    let mut advect_pipeline = AdvectPipeline::create(&context, advect_program);
     let mut impulse_pipeline = ImpulsePipeline::create(&context, force_program);
+    let mut splat_pipeline = SplatPipeline::create(&context, splat_program);
+    let mut source_pipeline = SourcePipeline::create(&context, source_program);
+    let mut buoyancy_pipeline = BuoyancyPipeline::create(&context, buoyancy_program);
     let mut divergence_pipeline = DivergencePipeline::create(&context, divergence_program);
     let mut jacobi_pipeline = JacobiPipeline::create(&context, jacobi_program);
     let mut boundary_pipeline = BoundaryPipeline::create(&context, boundary_program);
     let mut gradient_pipeline = GradientSubtractPipeline::create(&context, gradient_program);
+    let mut dye_display_pipeline = DyeDisplayPipeline::create(&context, dye_display_program);
+    let mut curl_pipeline = CurlPipeline::create(&context, curl_program);
+    let mut scalar_display_pipeline =
+        ScalarDisplayPipeline::create(&context, scalar_display_program);
+    let mut velocity_display_pipeline =
+        VelocityDisplayPipeline::create(&context, velocity_display_program);
+    let mut smoke_display_pipeline = SmokeDisplayPipeline::create(&context, smoke_display_program);
+    let mut arrow_pipeline = ArrowPipeline::create(&context, arrow_program);
+    let mut streamline_pipeline = StreamlinePipeline::create(&context, streamline_program);
 
-    let f = Rc::new(RefCell::new(None));
-    let g = f.clone();
-
-    let mut prev_mouse: (f32, f32) = (0.0, 1.0);
-    let mut prev_input_time = None::<f64>;
-    let mut prev_frame = None::<f64>;
+    let mut advection = Advection::create(&context, &quad_vert_shader);
+    let mut particles = Particles::create(&context, &quad_vert_shader);
 
     let quad = Quad::create(&context);
+    let lattice = display::make_lattice(OVERLAY_LATTICE);
+    let arrows = Mesh::create_instanced(&context, GL::LINES, 2, &display::ARROW_SHAPE, 2, &lattice);
+    let streamlines = Mesh::create_instanced(
+        &context,
+        GL::LINE_STRIP,
+        2,
+        &display::make_streamline(STREAMLINE_VERTICES),
+        2,
+        &lattice,
+    );
+
+    let mut fixed_step = FixedTimestep::new(STEP_MS, MAX_STEPS_PER_FRAME);
+    let mut recorder = Recorder::new(capture, "fluids");
+    let mut shown_mode = DisplayMode::Dye;
+    let mut sim_mode = SimMode::Fluid;
+    let mut field_range = 1.0;
+    let mut shown_overlay = Overlay::None;
+    let mut velocity_range = 1.0;
+    let mut script: Option<(PointerScript, Pointers)> = None;
+    // the JSON script was parsed from, to notice it being edited
+    let mut script_source = String::new();
+    let mut pointers = Pointers::default();
+    let mut input_log = InputLog::new(replay);
+    let mut sim_time = 0.0;
+
+    own_loop(AnimationLoop::start(move |frame| {
+        // switching deterministic mode either way, or editing the script while
+        // it's on, restarts from the initial state
+        let mut restart = false;
+        let deterministic = settings.deterministic.get_untracked();
+        let edited = deterministic
+            && settings
+                .script
+                .with_untracked(|json| *json != script_source);
+        if deterministic != script.is_some() || edited {
+            restart = true;
+            script = None;
+            if deterministic {
+                script_source = settings.script.get_untracked();
+                match PointerScript::parse(&script_source) {
+                    Ok(parsed) => script = Some((parsed, Pointers::default())),
+                    Err(err) => {
+                        console::log_1(&format!("Failed to parse pointer script: {err}").into());
+                        settings.deterministic.set(false);
+                    }
+                }
+            }
+        }
+
+        let recording = capture.is_recording();
+        let fixed_steps = recording || script.is_some();
+        let steps = match script {
+            Some(_) if clock.is_playing() => DETERMINISTIC_STEPS,
+            Some(_) => clock.take_step() as u32,
+            None if recording => 1,
+            None => fixed_step.advance(clock.advance(frame.dt)) + clock.take_step() as u32,
+        };
+
+        // live input goes through the log so it can be recorded or replaced
+        let live_inputs = std::mem::take(&mut *pointer_queue.borrow_mut());
+        let logged = input_log.frame(clock.is_playing(), steps, live_inputs);
+        let steps = logged.steps;
+
+        if restart || logged.restart {
+            for texture in [&velocity_texture, &pressure_texture, &smoke_texture] {
+                texture
+                    .read()
+                    .copy_from(&blank_texture)
+                    .expect("failed to clear sim");
+            }
+            let dye_side = dye_resolution as usize;
+            let dye = initial_dye(dye_side, dye_side);
+            dye_texture
+                .read()
+                .upload(GL::RGBA, GL::FLOAT, ArrayView::create(&dye));
+            previous_dye.copy_from(dye_texture.read()).unwrap();
+            particles.resize(&context, particles.count());
+            pointers = Pointers::default();
+            fixed_step = FixedTimestep::new(STEP_MS, MAX_STEPS_PER_FRAME);
+            sim_time = 0.0;
+        }
+        if let Some(bytes) = snapshots.take_load() {
+            match Snapshot::decode(&bytes)
+                .map_err(|err| err.to_string())
+                .and_then(|snapshot| {
+                    check_snapshot(&snapshot, sim_w, sim_h).map(|config| (snapshot, config))
+                }) {
+                Ok((snapshot, (config, Some(resolution)))) => {
+                    let wrap = boundary_mode.texture_wrap();
+                    let params = [
+                        (GL::TEXTURE_MIN_FILTER, GL::NEAREST),
+                        (GL::TEXTURE_MAG_FILTER, GL::NEAREST),
+                        wrap[0],
+                        wrap[1],
+                    ];
+                    let texture = |name| snapshot.texture(name).unwrap();
+                    velocity_texture = texture("velocity").create_texture(&context, &params);
+                    pressure_texture = texture("pressure").create_texture(&context, &params);
+                    smoke_texture = texture("smoke").create_texture(&context, &params);
+                    dye_texture = texture("dye").create_texture(&context, &params);
+                    previous_dye = texture("dye").create_texture(&context, &params);
+                    dye_scratch = None;
+                    dye_resolution = resolution;
+                    settings.dye_resolution.set(resolution);
+                    // set the local mode too so switching doesn't clear the loaded smoke
+                    sim_mode = config.sim_mode;
+                    config.apply(settings);
+                }
+                // a session snapshot, only the settings come back
+                Ok((_, (config, None))) => config.apply(settings),
+                Err(err) => console::log_1(&format!("Failed to load snapshot: {err}").into()),
+            }
+        }
+
+        for input in logged.inputs {
+            pointers.handle(input);
+        }
+
+        // One impulse per pointer that moved since the last step, scaled by
+        // how far the pointer travels during a single step at its current
+        // speed so the force doesn't depend on the frame rate
+        let mut impulse_count = 0;
+        let mut spawn = None;
+        if steps > 0 {
+            let mut moves = pointers.take_moves();
+            if let Some((script, scripted)) = script.as_mut() {
+                // live input is dropped while the script plays
+                script.play_until(sim_time + steps as f64 * STEP_MS, scripted);
+                moves = scripted.take_moves();
+            }
+            impulse_count = cmp::min(moves.len(), MAX_IMPULSES);
+            // particles are born under the first pointer that's stirring
+            spawn = moves
+                .first()
+                .map(|pointer| (pointer.position, force_radius));
+            let mut impulse_data = [0.0f32; MAX_IMPULSES * 2 * 4];
+            let (locations, colors) = impulse_data.split_at_mut(MAX_IMPULSES * 4);
+            for (idx, pointer) in moves.iter().take(impulse_count).enumerate() {
+                let color = settings.dye_mode.with_untracked(|mode| {
+                    settings.dye_color.with_untracked(|picked| {
+                        dye::splat_color(*mode, picked, sim_time / 1000.0, pointer.velocity)
+                    })
+                });
+                locations[idx * 4..idx * 4 + 4].copy_from_slice(&[
+                    pointer.position.0,
+                    pointer.position.1,
+                    pointer.velocity.0 * STEP_MS as f32,
+                    pointer.velocity.1 * STEP_MS as f32,
+                ]);
+                colors[idx * 4..idx * 4 + 4].copy_from_slice(&[color.0, color.1, color.2, 0.0]);
+            }
+            if impulse_count > 0 {
+                impulse_texture.upload(GL::RGBA, GL::FLOAT, ArrayView::create(&impulse_data));
+            }
+        }
+
+        let advection_mode = settings.advection.get_untracked();
+        let ambient = settings.ambient_temperature.get_untracked();
+        if settings.sim_mode.get_untracked() != sim_mode {
+            sim_mode = settings.sim_mode.get_untracked();
+            // every switch to smoke starts from clear air
+            smoke_texture
+                .read()
+                .copy_from(&blank_texture)
+                .expect("failed to clear smoke");
+        }
+        if settings.dye_resolution.get_untracked() != dye_resolution {
+            dye_resolution = settings.dye_resolution.get_untracked();
+            let dye_side = dye_resolution as usize;
+            let mut resized = make_initial_dye::<SwappableTexture>(dye_side, dye_side, &context);
+            // carry the dye over by drawing it into the new size
+            dye_display_pipeline.set_arguments(
+                &context,
+                dye_texture.read(),
+                dye_texture.read(),
+                dye_texture.read().texel_size(),
+                1.0,
+            );
+            quad.blit(Some(resized.write()));
+            resized.swap();
+            dye_texture = resized;
+            previous_dye = make_initial_dye::<BufferedTexture>(dye_side, dye_side, &context);
+            previous_dye.copy_from(dye_texture.read()).unwrap();
+            dye_scratch = None;
+            let wrap = boundary_mode.texture_wrap();
+            previous_dye.set_parameters(&wrap);
+            dye_texture.set_parameters(&wrap);
+        }
+        if settings.boundary.get_untracked() != boundary_mode {
+            boundary_mode = settings.boundary.get_untracked();
+            let offsets = boundary_mode.make_offsets(sim_w, sim_h, obstacles.as_deref());
+            boundary_texture.upload(GL::RGB, GL::FLOAT, ArrayView::create(&offsets));
+            // anything sampled across an edge has to wrap the same way
+            let wrap = boundary_mode.texture_wrap();
+            for texture in [&temp_texture, &field_texture, &previous_dye] {
+                texture.set_parameters(&wrap);
+            }
+            for texture in [
+                &pressure_texture,
+                &velocity_texture,
+                &dye_texture,
+                &smoke_texture,
+                &velocity_scratch,
+            ] {
+                texture.set_parameters(&wrap);
+            }
+            if let Some(scratch) = &dye_scratch {
+                scratch.set_parameters(&wrap);
+            }
+        }
+        match (advection_mode, &dye_scratch) {
+            (AdvectionMode::MacCormack, None) => {
+                let dye_side = dye_resolution as usize;
+                let scratch = make_initial_dye::<SwappableTexture>(dye_side, dye_side, &context);
+                scratch.set_parameters(&boundary_mode.texture_wrap());
+                dye_scratch = Some(scratch);
+            }
+            (AdvectionMode::SemiLagrangian, Some(_)) => dye_scratch = None,
+            _ => (),
+        }
+        let maccormack = advection_mode == AdvectionMode::MacCormack;
+        if let Some(bytes) = settings.picture.write_untracked().take() {
+            match Picture::decode(&bytes) {
+                Ok(picture) => {
+                    let dye = picture.to_dye(dye_resolution as usize);
+                    dye_texture
+                        .read()
+                        .upload(GL::RGBA, GL::FLOAT, ArrayView::create(&dye));
+                    previous_dye.copy_from(dye_texture.read()).unwrap();
+                    // start the flow over around the new picture
+                    velocity_texture
+                        .read()
+                        .copy_from(&blank_texture)
+                        .expect("failed to clear velocity");
+                    obstacles = settings
+                        .picture_obstacles
+                        .get_untracked()
+                        .then(|| picture.to_obstacles(sim_w, sim_h));
+                    let offsets = boundary_mode.make_offsets(sim_w, sim_h, obstacles.as_deref());
+                    boundary_texture.upload(GL::RGB, GL::FLOAT, ArrayView::create(&offsets));
+                }
+                Err(err) => console::log_1(&format!("Failed to decode image: {err}").into()),
+            }
+        }
+        let has_edges = boundary_mode.has_edges() || obstacles.is_some();
+        let particle_count = settings.particle_count.get_untracked();
+        if particle_count != particles.count() {
+            particles.resize(&context, particle_count);
+        }
+
+        for step in 0..steps {
+            // keep the state from before the final step to interpolate from
+            if step == steps - 1 {
+                previous_dye.copy_from(dye_texture.read()).unwrap();
+            }
+
+            // Velocity Boundary
+            if has_edges {
+                boundary_pipeline.set_arguments(
+                    &context,
+                    velocity_texture.read(),
+                    &boundary_texture,
+                    velocity_texture.read().texel_size(),
+                    boundary_mode.velocity_scale(),
+                );
+                quad.blit(Some(velocity_texture.write()));
+                velocity_texture.swap();
+            }
+
+            // Advect Velocity
+            advection.advect(
+                &context,
+                &quad,
+                &velocity_texture,
+                velocity_texture.read(),
+                maccormack.then_some(&velocity_scratch),
+                (timestep, 0.0),
+            );
+            velocity_texture.swap();
+
+            // Advect Dye
+            advection.advect(
+                &context,
+                &quad,
+                &dye_texture,
+                velocity_texture.read(),
+                dye_scratch.as_ref(),
+                (timestep, settings.dye_dissipation.get_untracked()),
+            );
+            dye_texture.swap();
 
-    *g.borrow_mut() = Some(Closure::new(move || {
-        let now = window().performance().unwrap().now();
-
-        // Velocity Boundary
-        boundary_pipeline.set_arguments(
-            &context,
-            velocity_texture.read(),
-            &boundary_texture,
-            velocity_texture.read().texel_size(),
-            -1.0,
-        );
-        quad.blit(Some(velocity_texture.write()));
-        velocity_texture.swap();
-
-        // Advect Velocity
-        advect_pipeline.set_arguments(
-            &context,
-            velocity_texture.read(),
-            velocity_texture.read(),
-            velocity_texture.read().texel_size(),
-            velocity_texture.read().texel_size(),
-            timestep,
-        );
-        quad.blit(Some(velocity_texture.write()));
-        velocity_texture.swap();
-
-        // Advect Dye
-        advect_pipeline.set_arguments(
-            &context,
-            dye_texture.read(),
-            velocity_texture.read(),
-            dye_texture.read().texel_size(),
-            velocity_texture.read().texel_size(),
-            timestep,
-        );
-        quad.blit(Some(dye_texture.write()));
-        dye_texture.swap();
-
-        // Add impulse
-        let cur_mouse: (f32, f32) = (
-            mouse.x.get_untracked().div(window_w as f64) as f32,
-            1.0.sub(mouse.y.get_untracked().div(window_h as f64)) as f32,
-        );
-        if cur_mouse != prev_mouse {
-            if prev_input_time.is_some() && prev_frame.is_some() && prev_input_time >= prev_frame {
+            if sim_mode == SimMode::Smoke {
+                // Advect Smoke
+                advection.advect(
+                    &context,
+                    &quad,
+                    &smoke_texture,
+                    velocity_texture.read(),
+                    maccormack.then_some(&velocity_scratch),
+                    (timestep, smoke::COOLING),
+                );
+                smoke_texture.swap();
+
+                // Add Heat and Smoke
+                source_pipeline.set_arguments(
+                    &context,
+                    smoke_texture.read(),
+                    &impulse_texture,
+                    (impulse_count as f32, force_radius),
+                    smoke::SOURCE_AMOUNT,
+                    smoke::EMITTER,
+                );
+                quad.blit(Some(smoke_texture.write()));
+                smoke_texture.swap();
+
+                // Buoyancy
+                buoyancy_pipeline.set_arguments(
+                    &context,
+                    velocity_texture.read(),
+                    smoke_texture.read(),
+                    (ambient, smoke::BUOYANCY, smoke::WEIGHT),
+                    timestep,
+                );
+                quad.blit(Some(velocity_texture.write()));
+                velocity_texture.swap();
+            }
+
+            // Add impulses
+            if impulse_count > 0 {
                 impulse_pipeline.set_arguments(
                     &context,
                     velocity_texture.read(),
-                    cur_mouse,
-                    (cur_mouse.0 - prev_mouse.0, cur_mouse.1 - prev_mouse.1),
+                    &impulse_texture,
+                    impulse_count as f32,
                     force_scale,
                     force_radius,
                 );
                 quad.blit(Some(velocity_texture.write()));
                 velocity_texture.swap();
+
+                splat_pipeline.set_arguments(
+                    &context,
+                    dye_texture.read(),
+                    &impulse_texture,
+                    impulse_count as f32,
+                    force_radius,
+                );
+                quad.blit(Some(dye_texture.write()));
+                dye_texture.swap();
             }
-            prev_input_time = Some(now);
-        }
-        prev_mouse = cur_mouse;
 
-        // Diffuse
-        temp_texture.copy_from(velocity_texture.read()).unwrap();
-        velocity_texture
-            .read()
-            .copy_from(&blank_texture)
-            .expect("failed to clear velocity");
+            // Diffuse
+            temp_texture.copy_from(velocity_texture.read()).unwrap();
+            velocity_texture
+                .read()
+                .copy_from(&blank_texture)
+                .expect("failed to clear velocity");
+
+            for _ in 0..30 {
+                jacobi_pipeline.set_arguments(
+                    &context,
+                    velocity_texture.read(),
+                    &temp_texture,
+                    velocity_texture.read().texel_size(),
+                    diffusion_alpha,
+                    diffusion_beta,
+                );
+                quad.blit(Some(velocity_texture.write()));
+                velocity_texture.swap();
+            }
 
-        for _ in 0..30 {
-            jacobi_pipeline.set_arguments(
+            // Compute Divergance
+            divergence_pipeline.set_arguments(
                 &context,
                 velocity_texture.read(),
-                &temp_texture,
                 velocity_texture.read().texel_size(),
-                diffusion_alpha,
-                diffusion_beta,
             );
-            quad.blit(Some(velocity_texture.write()));
-            velocity_texture.swap();
-        }
+            quad.blit(Some(&temp_texture));
 
-        // Compute Divergance
-        divergence_pipeline.set_arguments(
-            &context,
-            velocity_texture.read(),
-            velocity_texture.read().texel_size(),
-        );
-        quad.blit(Some(&temp_texture));
-
-        // Compute Pressure
-        pressure_texture
-            .read()
-            .copy_from(&blank_texture)
-            .expect("failed to clear pressure");
-
-        for _ in 0..40 {
-            boundary_pipeline.set_arguments(
+            // Compute Pressure
+            pressure_texture
+                .read()
+                .copy_from(&blank_texture)
+                .expect("failed to clear pressure");
+
+            for _ in 0..40 {
+                if has_edges {
+                    boundary_pipeline.set_arguments(
+                        &context,
+                        pressure_texture.read(),
+                        &boundary_texture,
+                        pressure_texture.read().texel_size(),
+                        boundary_mode.pressure_scale(),
+                    );
+                    quad.blit(Some(pressure_texture.write()));
+                    pressure_texture.swap();
+                }
+
+                jacobi_pipeline.set_arguments(
+                    &context,
+                    pressure_texture.read(),
+                    &temp_texture,
+                    pressure_texture.read().texel_size(),
+                    pressure_alpha,
+                    pressure_beta,
+                );
+                quad.blit(Some(pressure_texture.write()));
+                pressure_texture.swap();
+            }
+
+            // Reapply Boundaries
+            if has_edges {
+                boundary_pipeline.set_arguments(
+                    &context,
+                    velocity_texture.read(),
+                    &boundary_texture,
+                    velocity_texture.read().texel_size(),
+                    boundary_mode.velocity_scale(),
+                );
+                quad.blit(Some(velocity_texture.write()));
+                velocity_texture.swap();
+            }
+
+            // Gradient Subtraction
+            gradient_pipeline.set_arguments(
                 &context,
+                velocity_texture.read(),
                 pressure_texture.read(),
-                &boundary_texture,
-                pressure_texture.read().texel_size(),
-                1.0,
+                velocity_texture.read().texel_size(),
             );
-            quad.blit(Some(pressure_texture.write()));
-            pressure_texture.swap();
+            quad.blit(Some(velocity_texture.write()));
+            velocity_texture.swap();
 
-            jacobi_pipeline.set_arguments(
+            // Move Particles
+            let (wrap_x, wrap_y) = boundary_mode.periodic();
+            particles.step(
                 &context,
-                pressure_texture.read(),
-                &temp_texture,
-                pressure_texture.read().texel_size(),
-                pressure_alpha,
-                pressure_beta,
+                &quad,
+                velocity_texture.read(),
+                timestep,
+                spawn,
+                (wrap_x as u32 as f32, wrap_y as u32 as f32),
             );
-            quad.blit(Some(pressure_texture.write()));
-            pressure_texture.swap();
         }
 
-        // Reapply Boundaries
-        boundary_pipeline.set_arguments(
-            &context,
-            velocity_texture.read(),
-            &boundary_texture,
-            velocity_texture.read().texel_size(),
-            -1.0,
-        );
-        quad.blit(Some(velocity_texture.write()));
-        velocity_texture.swap();
-
-        // Gradient Subtraction
-        gradient_pipeline.set_arguments(
-            &context,
-            velocity_texture.read(),
-            pressure_texture.read(),
-            velocity_texture.read().texel_size(),
-        );
-        quad.blit(Some(velocity_texture.write()));
-        velocity_texture.swap();
-
-        // draw the dye
-        context.use_program(Some(quad_program.program()));
-        context.uniform1i(
-            quad_program.uniforms().get("u_texture").unwrap().into(),
-            dye_texture.read().attach(0),
-        );
+        sim_time += steps as f64 * STEP_MS;
 
+        // only written out on the frames something asks for it
+        let config = || {
+            let config = FluidConfig {
+                sim_mode,
+                advection: advection_mode,
+                boundary: boundary_mode,
+                dye_dissipation: settings.dye_dissipation.get_untracked(),
+                ambient_temperature: ambient,
+            };
+            serde_json::to_string(&config).expect("failed to write config")
+        };
+        // sessions only keep the settings, reading the fields back every few
+        // seconds would stall the sim
+        if snapshots.session_due() {
+            snapshots.store_session(&Snapshot {
+                kind: DemoKind::Fluids,
+                config: config(),
+                textures: Vec::new(),
+            });
+        }
+        if snapshots.take_save() {
+            let sim_field = |name, texture: &BufferedTexture| {
+                TextureData::from_rgba_f32(
+                    name,
+                    texture.width(),
+                    texture.height(),
+                    TextureFormat::Rg32F,
+                    &texture.read_rgba_f32(),
+                )
+            };
+            let snapshot = Snapshot {
+                kind: DemoKind::Fluids,
+                config: config(),
+                textures: vec![
+                    sim_field("velocity", velocity_texture.read()),
+                    sim_field("pressure", pressure_texture.read()),
+                    sim_field("smoke", smoke_texture.read()),
+                    TextureData::from_rgba_f32(
+                        "dye",
+                        dye_texture.read().width(),
+                        dye_texture.read().height(),
+                        TextureFormat::Rgba32F,
+                        &dye_texture.read().read_rgba_f32(),
+                    ),
+                ],
+            };
+            snapshots.save(&snapshot, "fluids");
+        }
+
+        // Display
+        let mode = settings.display_mode.get_untracked();
+        let field = match mode {
+            DisplayMode::Dye => None,
+            DisplayMode::Velocity => Some(velocity_texture.read()),
+            DisplayMode::Pressure => Some(pressure_texture.read()),
+            DisplayMode::Divergence => {
+                // what's left over after the projection, should be near zero
+                divergence_pipeline.set_arguments(
+                    &context,
+                    velocity_texture.read(),
+                    velocity_texture.read().texel_size(),
+                );
+                quad.blit(Some(&field_texture));
+                Some(&field_texture)
+            }
+            DisplayMode::Curl => {
+                curl_pipeline.set_arguments(
+                    &context,
+                    velocity_texture.read(),
+                    velocity_texture.read().texel_size(),
+                );
+                quad.blit(Some(&field_texture));
+                Some(&field_texture)
+            }
+        };
+
+        match field {
+            None if sim_mode == SimMode::Smoke => {
+                smoke_display_pipeline.set_arguments(
+                    &context,
+                    smoke_texture.read(),
+                    smoke_texture.read().texel_size(),
+                );
+            }
+            None => {
+                // draw the dye, part way between the last two steps
+                dye_display_pipeline.set_arguments(
+                    &context,
+                    &previous_dye,
+                    dye_texture.read(),
+                    dye_texture.read().texel_size(),
+                    if fixed_steps { 1.0 } else { fixed_step.alpha() },
+                );
+            }
+            Some(field) => {
+                if mode != shown_mode || frame.count % RANGE_INTERVAL == 0 {
+                    field_range = display::field_range(&field.read_rgba_f32(), mode);
+                }
+                if mode == DisplayMode::Velocity {
+                    velocity_display_pipeline.set_arguments(&context, field, field_range);
+                } else {
+                    scalar_display_pipeline.set_arguments(
+                        &context,
+                        field,
+                        field_range,
+                        settings.colormap.get_untracked() as u32 as f32,
+                    );
+                }
+            }
+        }
+        shown_mode = mode;
         quad.blit(None);
 
-        prev_frame = Some(now);
+        // Overlay
+        let overlay = settings.overlay.get_untracked();
+        let spacing = 1.0 / OVERLAY_LATTICE as f32;
+        match overlay {
+            Overlay::None => (),
+            Overlay::Arrows => {
+                if overlay != shown_overlay || frame.count % RANGE_INTERVAL == 0 {
+                    velocity_range = display::field_range(
+                        &velocity_texture.read().read_rgba_f32(),
+                        DisplayMode::Velocity,
+                    );
+                }
+                // the fastest arrow just reaches the next lattice point
+                arrow_pipeline.set_arguments(
+                    &context,
+                    velocity_texture.read(),
+                    spacing / velocity_range,
+                    spacing,
+                );
+                arrows.draw(None);
+            }
+            Overlay::Streamlines => {
+                // each line is long enough to cross a couple of lattice cells
+                streamline_pipeline.set_arguments(
+                    &context,
+                    velocity_texture.read(),
+                    2.0 * spacing / STREAMLINE_VERTICES as f32,
+                );
+                streamlines.draw(None);
+            }
+        }
+        shown_overlay = overlay;
 
-        window()
-            .request_animation_frame(
-                (f.borrow().as_ref().unwrap() as &Closure<dyn FnMut()>)
-                    .as_ref()
-                    .unchecked_ref(),
-            )
-            .expect("requestAnimationFrame failed");
+        particles.draw(&context);
+
+        recorder.capture(&context, STEP_MS);
     }));
+}
 
-    window()
-        .request_animation_frame(
-            (g.borrow().as_ref().unwrap() as &Closure<dyn FnMut()>)
-                .as_ref()
-                .unchecked_ref(),
-        )
-        .expect("requestAnimationFrame failed");
+// makes sure a snapshot has every field at sizes this sim can use before
+// anything gets replaced, returns its config and dye resolution, or no
+// resolution when the snapshot only holds settings
+fn check_snapshot(
+    snapshot: &Snapshot,
+    sim_w: usize,
+    sim_h: usize,
+) -> Result<(FluidConfig, Option<DyeResolution>), String> {
+    if snapshot.kind != DemoKind::Fluids {
+        return Err("snapshot is from a different demo".to_string());
+    }
+    let config = snapshot
+        .config::<FluidConfig>()
+        .map_err(|err| err.to_string())?;
+    if snapshot.textures.is_empty() {
+        return Ok((config, None));
+    }
+    for name in ["velocity", "pressure", "smoke"] {
+        match snapshot.texture(name) {
+            Some(texture)
+                if texture.format == TextureFormat::Rg32F
+                    && (texture.width as usize, texture.height as usize) == (sim_w, sim_h) => {}
+            _ => return Err(format!("{name} is missing or doesn't match the sim")),
+        }
+    }
+    let resolution = snapshot
+        .texture("dye")
+        .filter(|dye| dye.format == TextureFormat::Rgba32F && dye.width == dye.height)
+        .and_then(|dye| {
+            DyeResolution::ALL
+                .iter()
+                .find(|resolution| **resolution as u32 == dye.width)
+        })
+        .ok_or("dye is missing or isn't a supported size")?;
+    Ok((config, Some(*resolution)))
+}
+
+// glsl has no includes, so the helper goes in straight after the precision line
+fn with_bilerp(source: &str) -> String {
+    let (precision, rest) = source.split_once('\n').unwrap_or((source, ""));
+    format!("{precision}\n{BILERP}\n{rest}")
 }
 
 fn make_blank<T: FromJsView>(width: usize, height: usize, context: &WebGl2RenderingContext) -> T {
     const VALUES_PER_PIXEL: usize = 2;
-    let tex_data_size = width as usize * height as usize * VALUES_PER_PIXEL;
+    let tex_data_size = width * height * VALUES_PER_PIXEL;
     let texture_data = vec![0.0; tex_data_size];
 
     T::create(
-        &context,
+        context,
         GL::TEXTURE_2D,
         0,
-        GL::RG32F,
+        GL::RGB32F,
         width as i32,
         height as i32,
         0,
-        GL::RG,
+        GL::RGB,
         GL::FLOAT,
         Some(ArrayView::create(&texture_data)),
         &[
@@ -405,38 +1195,46 @@ fn make_blank<T: FromJsView>(width: usize, height: usize, context: &WebGl2Render
     )
 }
 
+// one column per impulse, the first row holds location and direction
+// and the second holds the dye colour to splat
+fn make_impulses(context: &WebGl2RenderingContext) -> BufferedTexture {
+    BufferedTexture::create(
+        context,
+        GL::TEXTURE_2D,
+        0,
+        GL::RGBA32F,
+        MAX_IMPULSES as i32,
+        2,
+        0,
+        GL::RGBA,
+        GL::FLOAT,
+        None::<Infallible>,
+        &[
+            (GL::TEXTURE_MIN_FILTER, GL::NEAREST),
+            (GL::TEXTURE_MAG_FILTER, GL::NEAREST),
+            (GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE),
+            (GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE),
+        ],
+    )
+}
+
 fn make_boundary_offsets(
+    mode: BoundaryMode,
     width: usize,
     height: usize,
     context: &WebGl2RenderingContext,
 ) -> BufferedTexture {
-    const VALUES_PER_PIXEL: usize = 2;
-    let tex_data_size = width as usize * height as usize * VALUES_PER_PIXEL;
-    let mut texture_data = vec![0.0; tex_data_size];
-
-    for (i, elem) in texture_data.iter_mut().enumerate() {
-        let pos = i % VALUES_PER_PIXEL;
-        let pixel = i / VALUES_PER_PIXEL;
-        let row = pixel / width;
-        let col = pixel % width;
-        *elem = match (pos, row, col) {
-            (0, .., 0) => 1.0,
-            (0, .., x) if x == width - 1 => -1.0,
-            (1, 0, ..) => 1.0,
-            (1, y, ..) if y == height - 1 => -1.0,
-            _ => 0.0,
-        }
-    }
+    let texture_data = mode.make_offsets(width, height, None);
 
-    return BufferedTexture::create(
+    BufferedTexture::create(
         context,
         GL::TEXTURE_2D,
         0,
-        GL::RG32F,
+        GL::RGB32F,
         width as i32,
         height as i32,
         0,
-        GL::RG,
+        GL::RGB,
         GL::FLOAT,
         Some(ArrayView::create(&texture_data)),
         &[
@@ -445,16 +1243,13 @@ fn make_boundary_offsets(
             (GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE),
             (GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE),
         ],
-    );
+    )
 }
 
-fn make_initial_dye(
-    width: usize,
-    height: usize,
-    context: &WebGl2RenderingContext,
-) -> SwappableTexture {
+// a green-blue disk in the middle, RGBA floats
+fn initial_dye(width: usize, height: usize) -> Vec<f32> {
     const VALUES_PER_PIXEL: usize = 4;
-    let tex_data_size = width as usize * height as usize * VALUES_PER_PIXEL;
+    let tex_data_size = width * height * VALUES_PER_PIXEL;
     let mut texture_data = vec![0.0; tex_data_size];
     for (i, elem) in texture_data.iter_mut().enumerate() {
         let pos = i % VALUES_PER_PIXEL;
@@ -462,9 +1257,9 @@ fn make_initial_dye(
         let row = pixel / width;
         let col = pixel % width;
         let h_width = width as f32 / 2.0;
-        let h_height = width as f32 / 2.0;
+        let h_height = height as f32 / 2.0;
         let radius = cmp::min(width, height) as f32 / 4.0;
-        let dist = ((row as f32 - h_width).powi(2) + (col as f32 - h_height).powi(2)).sqrt();
+        let dist = ((col as f32 - h_width).powi(2) + (row as f32 - h_height).powi(2)).sqrt();
         *elem = match pos {
             1 if dist < radius => (radius - dist) / radius,
             2 if dist < radius => 1.0,
@@ -472,8 +1267,17 @@ fn make_initial_dye(
             _ => 0.0,
         };
     }
+    texture_data
+}
+
+fn make_initial_dye<T: FromJsView>(
+    width: usize,
+    height: usize,
+    context: &WebGl2RenderingContext,
+) -> T {
+    let texture_data = initial_dye(width, height);
 
-    return SwappableTexture::create(
+    T::create(
         context,
         GL::TEXTURE_2D,
         0,
@@ -490,5 +1294,5 @@ fn make_initial_dye(
             (GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE),
             (GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE),
         ],
-    );
+    )
 }
@@ -0,0 +1,131 @@
+use utility::prelude::*;
+
+use web_sys::{WebGl2RenderingContext, WebGlShader};
+
+use serde::{Deserialize, Serialize};
+
+use crate::controls::SelectOption;
+
+type GL = WebGl2RenderingContext;
+
+render_pipeline!(AdvectPipeline, "../shaders/advect.frag");
+
+render_pipeline!(MacCormackPipeline, "../shaders/maccormack.frag");
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AdvectionMode {
+    SemiLagrangian,
+    MacCormack,
+}
+
+impl SelectOption for AdvectionMode {
+    const ALL: &'static [Self] = &[Self::SemiLagrangian, Self::MacCormack];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::SemiLagrangian => "Semi-Lagrangian",
+            Self::MacCormack => "MacCormack",
+        }
+    }
+}
+
+pub struct Advection {
+    advect_pipeline: AdvectPipeline,
+    maccormack_pipeline: MacCormackPipeline,
+}
+
+impl Advection {
+    pub fn create(context: &WebGl2RenderingContext, quad_vert_shader: &WebGlShader) -> Self {
+        let advect_frag_shader = compile_shader(
+            context,
+            GL::FRAGMENT_SHADER,
+            &super::with_bilerp(include_str!("../shaders/advect.frag")),
+        )
+        .unwrap();
+
+        let maccormack_frag_shader = compile_shader(
+            context,
+            GL::FRAGMENT_SHADER,
+            &super::with_bilerp(include_str!("../shaders/maccormack.frag")),
+        )
+        .unwrap();
+
+        let advect_program = Program::create(context, quad_vert_shader, &advect_frag_shader);
+        let maccormack_program =
+            Program::create(context, quad_vert_shader, &maccormack_frag_shader);
+
+        Advection {
+            advect_pipeline: AdvectPipeline::create(context, advect_program),
+            maccormack_pipeline: MacCormackPipeline::create(context, maccormack_program),
+        }
+    }
+
+    // advects the read side of target into its write side, the caller swaps.
+    // passing scratch picks MacCormack, which needs both halves of it matching
+    // target's size and format. velocity may be target's own read side, and
+    // the last argument is the timestep and how quickly target fades
+    pub fn advect(
+        &mut self,
+        context: &WebGl2RenderingContext,
+        quad: &Quad,
+        target: &SwappableTexture,
+        velocity: &BufferedTexture,
+        scratch: Option<&SwappableTexture>,
+        (timestep, dissipation): (f32, f32),
+    ) {
+        match scratch {
+            None => {
+                self.advect_pipeline.set_arguments(
+                    context,
+                    target.read(),
+                    velocity,
+                    target.read().texel_size(),
+                    velocity.texel_size(),
+                    (timestep, dissipation),
+                );
+                quad.blit(Some(target.write()));
+            }
+            Some(scratch) => {
+                let forward = scratch.read();
+                let backward = scratch.write();
+
+                self.advect_pipeline.set_arguments(
+                    context,
+                    target.read(),
+                    velocity,
+                    target.read().texel_size(),
+                    velocity.texel_size(),
+                    (timestep, 0.0),
+                );
+                quad.blit(Some(forward));
+
+                self.advect_pipeline.set_arguments(
+                    context,
+                    forward,
+                    velocity,
+                    forward.texel_size(),
+                    velocity.texel_size(),
+                    (-timestep, 0.0),
+                );
+                quad.blit(Some(backward));
+
+                let (target_texel, velocity_texel) =
+                    (target.read().texel_size(), velocity.texel_size());
+                self.maccormack_pipeline.set_arguments(
+                    context,
+                    target.read(),
+                    backward,
+                    velocity,
+                    (
+                        target_texel.0,
+                        target_texel.1,
+                        velocity_texel.0,
+                        velocity_texel.1,
+                    ),
+                    (timestep, dissipation),
+                );
+                quad.blit(Some(target.write()));
+            }
+        }
+    }
+}
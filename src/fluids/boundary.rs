@@ -0,0 +1,245 @@
+use web_sys::WebGl2RenderingContext;
+
+use serde::{Deserialize, Serialize};
+
+use crate::controls::SelectOption;
+
+type GL = WebGl2RenderingContext;
+
+// offset marking a solid cell with no single neighbour to copy, must match
+// SOLID_INTERIOR in boundary.frag
+const SOLID_INTERIOR: [f32; 2] = [2.0, 2.0];
+
+// third channel of the offsets, set on obstacle cells so they get the
+// obstacle scale whatever the edges of the domain do
+const OBSTACLE: f32 = 1.0;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BoundaryMode {
+    Walls,
+    PeriodicX,
+    PeriodicY,
+    Periodic,
+    Open,
+}
+
+impl SelectOption for BoundaryMode {
+    const ALL: &'static [Self] = &[
+        Self::Walls,
+        Self::PeriodicX,
+        Self::PeriodicY,
+        Self::Periodic,
+        Self::Open,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Walls => "Walls",
+            Self::PeriodicX => "Wrap X",
+            Self::PeriodicY => "Wrap Y",
+            Self::Periodic => "Wrap Both",
+            Self::Open => "Open",
+        }
+    }
+}
+
+impl BoundaryMode {
+    // whether the flow leaving one side comes back in the other, for x and y
+    pub fn periodic(&self) -> (bool, bool) {
+        match self {
+            Self::PeriodicX => (true, false),
+            Self::PeriodicY => (false, true),
+            Self::Periodic => (true, true),
+            Self::Walls | Self::Open => (false, false),
+        }
+    }
+
+    // with nothing but wrapped edges there are no boundary cells to fix up
+    pub fn has_edges(&self) -> bool {
+        self.periodic() != (true, true)
+    }
+
+    // wrap parameters for every texture the sim samples neighbours from
+    pub fn texture_wrap(&self) -> [(u32, u32); 2] {
+        let wrap = |periodic| {
+            if periodic {
+                GL::REPEAT
+            } else {
+                GL::CLAMP_TO_EDGE
+            }
+        };
+        let (x, y) = self.periodic();
+        [(GL::TEXTURE_WRAP_S, wrap(x)), (GL::TEXTURE_WRAP_T, wrap(y))]
+    }
+
+    // Walls reflect velocity and copy pressure so nothing crosses them,
+    // open edges copy velocity and zero the pressure so flow leaves freely.
+    // Scales are for domain edges, obstacle edges and solid interiors, and
+    // obstacles are walls in every mode while their insides stand still
+    pub fn velocity_scale(&self) -> (f32, f32, f32) {
+        match self {
+            Self::Open => (1.0, -1.0, 0.0),
+            _ => (-1.0, -1.0, 0.0),
+        }
+    }
+
+    pub fn pressure_scale(&self) -> (f32, f32, f32) {
+        match self {
+            Self::Open => (-1.0, 1.0, 1.0),
+            _ => (1.0, 1.0, 1.0),
+        }
+    }
+
+    // RGB texels, the offset from every edge cell to its interior neighbour
+    // and whether it's an obstacle. Edges on periodic axes are left at zero
+    // so the boundary pass skips them. Solid obstacle cells next to fluid
+    // point at the fluid instead, and ones with fluid on neither or both
+    // opposite sides get SOLID_INTERIOR
+    pub fn make_offsets(
+        &self,
+        width: usize,
+        height: usize,
+        obstacles: Option<&[bool]>,
+    ) -> Vec<f32> {
+        let (periodic_x, periodic_y) = self.periodic();
+        let solid = |col: usize, row: usize| obstacles.is_some_and(|mask| mask[row * width + col]);
+        (0..width * height)
+            .flat_map(|pixel| {
+                let row = pixel / width;
+                let col = pixel % width;
+                if solid(col, row) {
+                    let open = |col: Option<usize>, row: Option<usize>| match (col, row) {
+                        (Some(col), Some(row)) if col < width && row < height => !solid(col, row),
+                        _ => false,
+                    };
+                    let x = open(col.checked_add(1), Some(row)) as i32
+                        - open(col.checked_sub(1), Some(row)) as i32;
+                    let y = open(Some(col), row.checked_add(1)) as i32
+                        - open(Some(col), row.checked_sub(1)) as i32;
+                    if (x, y) == (0, 0) {
+                        return [SOLID_INTERIOR[0], SOLID_INTERIOR[1], OBSTACLE];
+                    }
+                    return [x as f32, y as f32, OBSTACLE];
+                }
+                let x = match col {
+                    _ if periodic_x => 0.0,
+                    0 => 1.0,
+                    x if x == width - 1 => -1.0,
+                    _ => 0.0,
+                };
+                let y = match row {
+                    _ if periodic_y => 0.0,
+                    0 => 1.0,
+                    y if y == height - 1 => -1.0,
+                    _ => 0.0,
+                };
+                [x, y, 0.0]
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texel(offsets: &[f32], width: usize, col: usize, row: usize) -> [f32; 3] {
+        let idx = (row * width + col) * 3;
+        [offsets[idx], offsets[idx + 1], offsets[idx + 2]]
+    }
+
+    fn offset(offsets: &[f32], width: usize, col: usize, row: usize) -> [f32; 2] {
+        let [x, y, _] = texel(offsets, width, col, row);
+        [x, y]
+    }
+
+    // the scale boundary.frag applies to a texel, None where it leaves the
+    // cell alone
+    fn scale_at(texel: [f32; 3], scale: (f32, f32, f32)) -> Option<f32> {
+        match texel {
+            [x, y, _] if [x, y] == SOLID_INTERIOR => Some(scale.2),
+            [0.0, 0.0, _] => None,
+            [_, _, OBSTACLE] => Some(scale.1),
+            _ => Some(scale.0),
+        }
+    }
+
+    #[test]
+    fn walls_point_inwards() {
+        let offsets = BoundaryMode::Walls.make_offsets(3, 3, None);
+        assert_eq!(offset(&offsets, 3, 0, 0), [1.0, 1.0]);
+        assert_eq!(offset(&offsets, 3, 2, 1), [-1.0, 0.0]);
+        assert_eq!(offset(&offsets, 3, 1, 2), [0.0, -1.0]);
+        assert_eq!(offset(&offsets, 3, 1, 1), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn periodic_axes_are_skipped() {
+        let offsets = BoundaryMode::PeriodicX.make_offsets(3, 3, None);
+        assert_eq!(offset(&offsets, 3, 0, 0), [0.0, 1.0]);
+        assert_eq!(offset(&offsets, 3, 2, 2), [0.0, -1.0]);
+        let offsets = BoundaryMode::Periodic.make_offsets(3, 3, None);
+        assert!(offsets.iter().all(|texel| *texel == 0.0));
+    }
+
+    #[test]
+    fn solid_cells_point_at_fluid() {
+        // a 2 x 2 block in the middle of a 4 x 4 grid
+        let mask: Vec<bool> = (0..16)
+            .map(|idx| (1..3).contains(&(idx % 4)) && (1..3).contains(&(idx / 4)))
+            .collect();
+        let offsets = BoundaryMode::Periodic.make_offsets(4, 4, Some(&mask));
+        assert_eq!(offset(&offsets, 4, 1, 1), [-1.0, -1.0]);
+        assert_eq!(offset(&offsets, 4, 2, 2), [1.0, 1.0]);
+        assert_eq!(offset(&offsets, 4, 0, 0), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn enclosed_solid_is_interior() {
+        let mask = vec![true; 9];
+        let offsets = BoundaryMode::Walls.make_offsets(3, 3, Some(&mask));
+        assert_eq!(offset(&offsets, 3, 1, 1), SOLID_INTERIOR);
+        // cells on the edge of the grid have nothing outside them either
+        assert_eq!(offset(&offsets, 3, 0, 0), SOLID_INTERIOR);
+    }
+
+    #[test]
+    fn thin_wall_is_interior() {
+        // a one cell wide wall down the middle, fluid on both sides of it
+        let mask: Vec<bool> = (0..9).map(|idx| idx % 3 == 1).collect();
+        let offsets = BoundaryMode::Periodic.make_offsets(3, 3, Some(&mask));
+        assert_eq!(offset(&offsets, 3, 1, 1), SOLID_INTERIOR);
+        assert_eq!(offset(&offsets, 3, 0, 1), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn obstacles_are_flagged() {
+        let mut mask = vec![false; 9];
+        mask[4] = true;
+        let offsets = BoundaryMode::Walls.make_offsets(3, 3, Some(&mask));
+        assert_eq!(texel(&offsets, 3, 0, 1), [1.0, 0.0, 0.0]);
+        assert_eq!(
+            texel(&offsets, 3, 1, 1),
+            [SOLID_INTERIOR[0], SOLID_INTERIOR[1], OBSTACLE]
+        );
+    }
+
+    #[test]
+    fn open_obstacles_are_walls() {
+        // a solid cell with fluid to its right, at the middle of an open edge
+        let mut mask = vec![false; 9];
+        mask[3] = true;
+        let mode = BoundaryMode::Open;
+        let offsets = mode.make_offsets(3, 3, Some(&mask));
+        let obstacle = texel(&offsets, 3, 0, 1);
+        assert_eq!(obstacle, [1.0, 0.0, OBSTACLE]);
+        // no-slip velocity and zero-gradient pressure, so nothing gets through
+        assert_eq!(scale_at(obstacle, mode.velocity_scale()), Some(-1.0));
+        assert_eq!(scale_at(obstacle, mode.pressure_scale()), Some(1.0));
+
+        // while the open domain edge next to it still lets flow out
+        let edge = texel(&offsets, 3, 0, 0);
+        assert_eq!(scale_at(edge, mode.velocity_scale()), Some(1.0));
+        assert_eq!(scale_at(edge, mode.pressure_scale()), Some(-1.0));
+    }
+}
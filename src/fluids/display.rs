@@ -0,0 +1,154 @@
+use crate::controls::SelectOption;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DisplayMode {
+    Dye,
+    Velocity,
+    Pressure,
+    Divergence,
+    Curl,
+}
+
+impl SelectOption for DisplayMode {
+    const ALL: &'static [Self] = &[
+        Self::Dye,
+        Self::Velocity,
+        Self::Pressure,
+        Self::Divergence,
+        Self::Curl,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Dye => "Dye",
+            Self::Velocity => "Velocity",
+            Self::Pressure => "Pressure",
+            Self::Divergence => "Divergence",
+            Self::Curl => "Curl",
+        }
+    }
+}
+
+// discriminants are what display_scalar.frag expects in u_colormap
+#[derive(Clone, Copy, PartialEq)]
+pub enum Colormap {
+    Diverging = 0,
+    Viridis = 1,
+}
+
+impl SelectOption for Colormap {
+    const ALL: &'static [Self] = &[Self::Diverging, Self::Viridis];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Diverging => "Blue-Red",
+            Self::Viridis => "Viridis",
+        }
+    }
+}
+
+// keeps an empty field from dividing by zero in the shaders
+const MIN_RANGE: f32 = 1e-6;
+
+// largest magnitude in a field read back as rgba floats, velocity uses
+// the length of the first two channels and everything else just the first
+pub fn field_range(pixels: &[f32], mode: DisplayMode) -> f32 {
+    pixels
+        .chunks_exact(4)
+        .map(|pixel| match mode {
+            DisplayMode::Velocity => (pixel[0].powi(2) + pixel[1].powi(2)).sqrt(),
+            _ => pixel[0].abs(),
+        })
+        .filter(|value| value.is_finite())
+        .fold(MIN_RANGE, f32::max)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Overlay {
+    None,
+    Arrows,
+    Streamlines,
+}
+
+impl SelectOption for Overlay {
+    const ALL: &'static [Self] = &[Self::None, Self::Arrows, Self::Streamlines];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Arrows => "Arrows",
+            Self::Streamlines => "Streamlines",
+        }
+    }
+}
+
+// line segments of an arrow pointing along +x with unit length
+pub const ARROW_SHAPE: [f32; 12] = [
+    0.0, 0.0, 1.0, 0.0, //
+    1.0, 0.0, 0.7, 0.2, //
+    1.0, 0.0, 0.7, -0.2,
+];
+
+// the centres of a count x count grid of cells covering uv space
+pub fn make_lattice(count: usize) -> Vec<f32> {
+    (0..count * count)
+        .flat_map(|idx| {
+            [
+                ((idx % count) as f32 + 0.5) / count as f32,
+                ((idx / count) as f32 + 0.5) / count as f32,
+            ]
+        })
+        .collect()
+}
+
+// each vertex is its step number along the line and how far along
+// the line that is as a fraction
+pub fn make_streamline(vertices: usize) -> Vec<f32> {
+    (0..vertices)
+        .flat_map(|idx| [idx as f32, idx as f32 / (vertices - 1) as f32])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixels(values: &[[f32; 2]]) -> Vec<f32> {
+        values
+            .iter()
+            .flat_map(|[x, y]| [*x, *y, 0.0, 1.0])
+            .collect()
+    }
+
+    #[test]
+    fn range_of_known_field() {
+        let field = pixels(&[[0.5, 0.0], [-2.0, 0.0], [1.5, 3.0], [0.0, -1.0]]);
+        // scalars only look at the first channel, either sign
+        assert_eq!(field_range(&field, DisplayMode::Pressure), 2.0);
+        assert_eq!(field_range(&field, DisplayMode::Curl), 2.0);
+        let velocity = pixels(&[[3.0, -4.0], [1.0, 1.0]]);
+        assert_eq!(field_range(&velocity, DisplayMode::Velocity), 5.0);
+    }
+
+    #[test]
+    fn constant_field_has_a_range() {
+        let still = pixels(&[[0.0, 0.0]; 16]);
+        for mode in DisplayMode::ALL {
+            assert_eq!(field_range(&still, *mode), MIN_RANGE);
+        }
+        let uniform = pixels(&[[0.25, 0.0]; 16]);
+        assert_eq!(field_range(&uniform, DisplayMode::Divergence), 0.25);
+        assert_eq!(field_range(&[], DisplayMode::Pressure), MIN_RANGE);
+    }
+
+    #[test]
+    fn non_finite_values_are_skipped() {
+        let field = pixels(&[[f32::NAN, 0.0], [0.75, f32::NAN], [f32::INFINITY, 0.0]]);
+        assert_eq!(field_range(&field, DisplayMode::Pressure), 0.75);
+        // a NaN in either channel spoils a velocity's length
+        let velocity = pixels(&[[f32::NAN, 1.0], [0.0, 0.5]]);
+        assert_eq!(field_range(&velocity, DisplayMode::Velocity), 0.5);
+        let nothing = pixels(&[[f32::NAN, f32::NAN]; 4]);
+        assert_eq!(field_range(&nothing, DisplayMode::Velocity), MIN_RANGE);
+    }
+}
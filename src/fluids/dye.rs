@@ -0,0 +1,167 @@
+use std::f32::consts::TAU;
+
+use crate::controls::SelectOption;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DyeColorMode {
+    Fixed,
+    Rainbow,
+    Velocity,
+    Picked,
+}
+
+impl SelectOption for DyeColorMode {
+    const ALL: &'static [Self] = &[Self::Fixed, Self::Rainbow, Self::Velocity, Self::Picked];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Fixed => "Fixed",
+            Self::Rainbow => "Rainbow",
+            Self::Velocity => "Velocity",
+            Self::Picked => "Picked",
+        }
+    }
+}
+
+// side of the square dye texture, independent of both the sim and the canvas
+#[derive(Clone, Copy, PartialEq)]
+pub enum DyeResolution {
+    Low = 256,
+    Medium = 512,
+    High = 1024,
+    Ultra = 2048,
+}
+
+impl SelectOption for DyeResolution {
+    const ALL: &'static [Self] = &[Self::Low, Self::Medium, Self::High, Self::Ultra];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Low => "256²",
+            Self::Medium => "512²",
+            Self::High => "1024²",
+            Self::Ultra => "2048²",
+        }
+    }
+}
+
+const FIXED_COLOR: (f32, f32, f32) = (0.0, 0.8, 1.0);
+
+// seconds for the rainbow to cycle through every hue
+const RAINBOW_PERIOD: f64 = 5.0;
+
+// pointer speed (in uv per millisecond) that gets the brightest velocity colour
+const FULL_SPEED: f32 = 0.001;
+
+// dye added per splat, the splat is added on top of what's already
+// there so keep this low or the centre saturates immediately
+const SPLAT_INTENSITY: f32 = 0.3;
+
+pub fn splat_color(
+    mode: DyeColorMode,
+    picked: &str,
+    seconds: f64,
+    velocity: (f32, f32),
+) -> (f32, f32, f32) {
+    let color = match mode {
+        DyeColorMode::Fixed => FIXED_COLOR,
+        DyeColorMode::Rainbow => hsv_to_rgb((seconds / RAINBOW_PERIOD).fract() as f32, 1.0, 1.0),
+        DyeColorMode::Velocity => {
+            let hue = (velocity.1.atan2(velocity.0) / TAU).rem_euclid(1.0);
+            let speed = (velocity.0.powi(2) + velocity.1.powi(2)).sqrt();
+            hsv_to_rgb(hue, 1.0, (speed / FULL_SPEED).min(1.0))
+        }
+        DyeColorMode::Picked => parse_hex_color(picked).unwrap_or(FIXED_COLOR),
+    };
+    (
+        color.0 * SPLAT_INTENSITY,
+        color.1 * SPLAT_INTENSITY,
+        color.2 * SPLAT_INTENSITY,
+    )
+}
+
+pub fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> (f32, f32, f32) {
+    let sector = hue.rem_euclid(1.0) * 6.0;
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    (r + m, g + m, b + m)
+}
+
+// parses the "#rrggbb" strings that <input type="color"> produces
+pub fn parse_hex_color(hex: &str) -> Option<(f32, f32, f32)> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |idx: usize| {
+        u8::from_str_radix(hex.get(idx..idx + 2)?, 16)
+            .ok()
+            .map(|c| c as f32 / 255.0)
+    };
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: (f32, f32, f32), expected: (f32, f32, f32)) {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+        assert!(
+            close(actual.0, expected.0)
+                && close(actual.1, expected.1)
+                && close(actual.2, expected.2),
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn primary_hues() {
+        assert_close(hsv_to_rgb(0.0, 1.0, 1.0), (1.0, 0.0, 0.0));
+        assert_close(hsv_to_rgb(1.0 / 3.0, 1.0, 1.0), (0.0, 1.0, 0.0));
+        assert_close(hsv_to_rgb(2.0 / 3.0, 1.0, 1.0), (0.0, 0.0, 1.0));
+        assert_close(hsv_to_rgb(1.0 / 6.0, 1.0, 1.0), (1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn hue_wraps() {
+        assert_close(hsv_to_rgb(1.25, 1.0, 1.0), hsv_to_rgb(0.25, 1.0, 1.0));
+        assert_close(hsv_to_rgb(-0.75, 1.0, 1.0), hsv_to_rgb(0.25, 1.0, 1.0));
+    }
+
+    #[test]
+    fn saturation_and_value() {
+        assert_close(hsv_to_rgb(0.4, 0.0, 0.5), (0.5, 0.5, 0.5));
+        assert_close(hsv_to_rgb(0.0, 0.5, 1.0), (1.0, 0.5, 0.5));
+        assert_close(hsv_to_rgb(0.7, 1.0, 0.0), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn hex_colors() {
+        assert_close(
+            parse_hex_color("#ff8000").unwrap(),
+            (1.0, 128.0 / 255.0, 0.0),
+        );
+        assert_close(parse_hex_color("#FFFFFF").unwrap(), (1.0, 1.0, 1.0));
+        assert_close(parse_hex_color("#000000").unwrap(), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn bad_hex_colors() {
+        assert_eq!(parse_hex_color("ff8000"), None);
+        assert_eq!(parse_hex_color("#ff80"), None);
+        assert_eq!(parse_hex_color("#ff80001"), None);
+        assert_eq!(parse_hex_color("#gg8000"), None);
+        // six bytes but not six characters
+        assert_eq!(parse_hex_color("#ff€0"), None);
+    }
+}
@@ -0,0 +1,199 @@
+use utility::prelude::*;
+
+use web_sys::{WebGl2RenderingContext, WebGlShader};
+
+use crate::controls::SelectOption;
+
+type GL = WebGl2RenderingContext;
+
+render_pipeline!(ParticleUpdatePipeline, "../shaders/particles_update.frag");
+
+render_pipeline!(ParticleDrawPipeline, "../shaders/particles.vert");
+
+// average number of steps a particle lives for, each one gets
+// somewhere between half and one and a half times this
+const LIFETIME: f32 = 240.0;
+
+const POINT_SIZE: f32 = 2.0;
+
+// the side of the square particle texture, so the count is this squared
+#[derive(Clone, Copy, PartialEq)]
+pub enum ParticleCount {
+    Off = 0,
+    Few = 128,
+    Some = 256,
+    Many = 512,
+    Million = 1024,
+}
+
+impl SelectOption for ParticleCount {
+    const ALL: &'static [Self] = &[Self::Off, Self::Few, Self::Some, Self::Many, Self::Million];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Few => "16K",
+            Self::Some => "65K",
+            Self::Many => "262K",
+            Self::Million => "1M",
+        }
+    }
+}
+
+struct ParticleState {
+    positions: SwappableTexture,
+    mesh: Mesh,
+}
+
+pub struct Particles {
+    update_pipeline: ParticleUpdatePipeline,
+    draw_pipeline: ParticleDrawPipeline,
+    count: ParticleCount,
+    state: Option<ParticleState>,
+    seed: f32,
+}
+
+impl Particles {
+    pub fn create(context: &WebGl2RenderingContext, quad_vert_shader: &WebGlShader) -> Self {
+        let update_frag_shader = compile_shader(
+            context,
+            GL::FRAGMENT_SHADER,
+            &super::with_bilerp(include_str!("../shaders/particles_update.frag")),
+        )
+        .unwrap();
+
+        let draw_vert_shader = compile_shader(
+            context,
+            GL::VERTEX_SHADER,
+            include_str!("../shaders/particles.vert"),
+        )
+        .unwrap();
+
+        let draw_frag_shader = compile_shader(
+            context,
+            GL::FRAGMENT_SHADER,
+            include_str!("../shaders/particles.frag"),
+        )
+        .unwrap();
+
+        let update_program = Program::create(context, quad_vert_shader, &update_frag_shader);
+        let draw_program = Program::create(context, &draw_vert_shader, &draw_frag_shader);
+
+        Particles {
+            update_pipeline: ParticleUpdatePipeline::create(context, update_program),
+            draw_pipeline: ParticleDrawPipeline::create(context, draw_program),
+            count: ParticleCount::Off,
+            state: None,
+            seed: 0.0,
+        }
+    }
+
+    pub fn count(&self) -> ParticleCount {
+        self.count
+    }
+
+    pub fn resize(&mut self, context: &WebGl2RenderingContext, count: ParticleCount) {
+        self.count = count;
+        self.seed = 0.0;
+        // drop the old textures before making the new ones
+        self.state = None;
+        let side = count as usize;
+        if side == 0 {
+            return;
+        }
+        self.state = Some(ParticleState {
+            positions: make_particles(context, side),
+            mesh: Mesh::create(context, GL::POINTS, 2, &make_texel_coords(side)),
+        });
+    }
+
+    // moves every particle one step through the velocity field, particles that
+    // die come back around spawn if there is one or anywhere if not. wrap is
+    // 1 on axes where particles leaving one side come back in the other
+    pub fn step(
+        &mut self,
+        context: &WebGl2RenderingContext,
+        quad: &Quad,
+        velocity: &BufferedTexture,
+        timestep: f32,
+        spawn: Option<((f32, f32), f32)>,
+        wrap: (f32, f32),
+    ) {
+        let Some(state) = self.state.as_mut() else {
+            return;
+        };
+        // golden ratio steps keep successive seeds far apart
+        self.seed = (self.seed + 0.618_034).fract();
+        let texel_size = velocity.texel_size();
+        let spawn = spawn.map_or((0.0, 0.0, 0.0, 0.0), |((x, y), radius)| (x, y, radius, 1.0));
+        self.update_pipeline.set_arguments(
+            context,
+            state.positions.read(),
+            velocity,
+            (texel_size.0, texel_size.1, wrap.0, wrap.1),
+            (timestep, LIFETIME, self.seed),
+            spawn,
+        );
+        quad.blit(Some(state.positions.write()));
+        state.positions.swap();
+    }
+
+    pub fn draw(&mut self, context: &WebGl2RenderingContext) {
+        let Some(state) = self.state.as_ref() else {
+            return;
+        };
+        self.draw_pipeline
+            .set_arguments(context, state.positions.read(), POINT_SIZE);
+        state.mesh.draw(None);
+    }
+}
+
+// the centre of every texel in a side x side texture, one vertex per particle
+fn make_texel_coords(side: usize) -> Vec<f32> {
+    (0..side * side)
+        .flat_map(|idx| {
+            [
+                ((idx % side) as f32 + 0.5) / side as f32,
+                ((idx / side) as f32 + 0.5) / side as f32,
+            ]
+        })
+        .collect()
+}
+
+fn make_particles(context: &WebGl2RenderingContext, side: usize) -> SwappableTexture {
+    // xorshift, the starting spread doesn't need to be anything special
+    let mut rng = 0x9e37_79b9u32;
+    let mut random = move || {
+        rng ^= rng << 13;
+        rng ^= rng >> 17;
+        rng ^= rng << 5;
+        rng as f32 / u32::MAX as f32
+    };
+
+    // random ages so they don't all die on the same step
+    let texture_data: Vec<f32> = (0..side * side)
+        .flat_map(|_| {
+            let lifetime = LIFETIME * (0.5 + random());
+            [random(), random(), lifetime * random(), lifetime]
+        })
+        .collect();
+
+    SwappableTexture::create(
+        context,
+        GL::TEXTURE_2D,
+        0,
+        GL::RGBA32F,
+        side as i32,
+        side as i32,
+        0,
+        GL::RGBA,
+        GL::FLOAT,
+        Some(ArrayView::create(&texture_data)),
+        &[
+            (GL::TEXTURE_MIN_FILTER, GL::NEAREST),
+            (GL::TEXTURE_MAG_FILTER, GL::NEAREST),
+            (GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE),
+            (GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE),
+        ],
+    )
+}
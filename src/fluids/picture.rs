@@ -0,0 +1,117 @@
+use image::imageops::{self, FilterType};
+use image::{ImageResult, RgbaImage};
+
+// pixels with less alpha than this are solid when the image is used as obstacles
+const SOLID_ALPHA: u8 = 128;
+
+// a decoded image, stretched to a square and flipped so the first row is
+// the bottom one like GL expects
+pub struct Picture {
+    pixels: RgbaImage,
+}
+
+impl Picture {
+    pub fn decode(bytes: &[u8]) -> ImageResult<Self> {
+        let pixels = image::load_from_memory(bytes)?.to_rgba8();
+        Ok(Picture {
+            pixels: imageops::flip_vertical(&pixels),
+        })
+    }
+
+    // RGBA floats at side x side, alpha is always one since it's only
+    // there to mark obstacles
+    pub fn to_dye(&self, side: usize) -> Vec<f32> {
+        self.resample(side, side)
+            .pixels()
+            .flat_map(|pixel| {
+                let [r, g, b, _] = pixel.0;
+                [r, g, b].map(|c| c as f32 / 255.0).into_iter().chain([1.0])
+            })
+            .collect()
+    }
+
+    // true for every cell of a width x height grid the fluid can't enter
+    pub fn to_obstacles(&self, width: usize, height: usize) -> Vec<bool> {
+        self.resample(width, height)
+            .pixels()
+            .map(|pixel| pixel.0[3] < SOLID_ALPHA)
+            .collect()
+    }
+
+    fn resample(&self, width: usize, height: usize) -> RgbaImage {
+        imageops::resize(
+            &self.pixels,
+            width as u32,
+            height as u32,
+            FilterType::Triangle,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageFormat, Rgba};
+
+    use super::*;
+
+    fn png(pixels: RgbaImage) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgba8(pixels)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    // red on the top row and blue on the bottom one, as the image is stored
+    fn red_over_blue() -> Picture {
+        let pixels = RgbaImage::from_fn(2, 2, |_, y| {
+            if y == 0 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([0, 0, 255, 255])
+            }
+        });
+        Picture::decode(&png(pixels)).unwrap()
+    }
+
+    #[test]
+    fn bottom_row_first() {
+        let dye = red_over_blue().to_dye(2);
+        assert_eq!(dye.len(), 2 * 2 * 4);
+        assert_eq!(dye[..4], [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(dye[12..], [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn dye_resamples() {
+        let dye = red_over_blue().to_dye(8);
+        assert_eq!(dye.len(), 8 * 8 * 4);
+        assert!(dye.chunks(4).all(|pixel| pixel[3] == 1.0));
+    }
+
+    #[test]
+    fn alpha_is_ignored_by_dye() {
+        let pixels = RgbaImage::from_pixel(2, 2, Rgba([0, 255, 0, 0]));
+        let dye = Picture::decode(&png(pixels)).unwrap().to_dye(2);
+        assert!(dye.chunks(4).all(|pixel| pixel == [0.0, 1.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn transparent_is_solid() {
+        // transparent on the left half, opaque on the right
+        let pixels = RgbaImage::from_fn(4, 4, |x, _| Rgba([0, 0, 0, if x < 2 { 0 } else { 255 }]));
+        let obstacles = Picture::decode(&png(pixels)).unwrap().to_obstacles(4, 2);
+        assert_eq!(
+            obstacles,
+            [true, true, false, false, true, true, false, false]
+        );
+    }
+
+    #[test]
+    fn bad_bytes() {
+        assert!(Picture::decode(b"not an image").is_err());
+        assert!(Picture::decode(&[]).is_err());
+    }
+}
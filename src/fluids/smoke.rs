@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+use crate::controls::SelectOption;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SimMode {
+    Fluid,
+    Smoke,
+}
+
+impl SelectOption for SimMode {
+    const ALL: &'static [Self] = &[Self::Fluid, Self::Smoke];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Fluid => "Fluid",
+            Self::Smoke => "Smoke",
+        }
+    }
+}
+
+// upward acceleration per step for each degree above ambient
+pub const BUOYANCY: f32 = 0.05;
+
+// downward acceleration per step for each unit of smoke density
+pub const WEIGHT: f32 = 0.02;
+
+// temperature and density added per step at the centre of a source
+pub const SOURCE_AMOUNT: (f32, f32) = (0.05, 0.03);
+
+// a fire that always burns near the bottom of the canvas: centre,
+// radius and strength as a multiple of SOURCE_AMOUNT
+pub const EMITTER: (f32, f32, f32, f32) = (0.5, 0.08, 0.04, 1.0);
+
+// how fast heat and smoke fade, passed to advection as dissipation
+pub const COOLING: f32 = 0.02;
@@ -1,34 +1,420 @@
-use super::common::*;
+mod camera;
+mod edit;
+mod pattern;
+mod population;
+mod reference;
+mod rule;
+mod speed;
+mod stats;
+
+use utility::prelude::*;
+
+use utility_macro::render_pipeline;
+
+use std::cell::RefCell;
+use std::rc::Rc;
 
 use leptos::html::Canvas;
 use leptos::prelude::*;
 use leptos::wasm_bindgen::prelude::*;
 
-use web_sys::WebGl2RenderingContext;
+use leptos_use::use_event_listener;
+use serde::{Deserialize, Serialize};
+use web_sys::{HtmlInputElement, WebGl2RenderingContext, console};
+
+use crate::capture::{Capture, CaptureControls, Recorder, download};
+use crate::controls::{EnumSelect, SelectOption, read_file};
+use crate::pointers::{PointerQueue, use_pointers};
+use crate::snapshot::{
+    DemoKind, Snapshot, SnapshotControls, Snapshots, TextureData, TextureFormat,
+};
+use crate::transport::{SimClock, Transport, own_loop};
+use camera::{Camera, WheelZoom};
+
+use edit::{BrushSize, EditOptions, EditTool, Editor, Rotation};
+use pattern::{Pattern, PatternFormat};
+use population::Population;
+use rule::{Counts, Neighbourhood, Rule};
+use speed::{Pacer, Rate};
+use stats::{Settled, Stats};
 
 type GL = WebGl2RenderingContext;
 
+const CANVAS_SIZE: usize = 512;
+
+// height of the population sparkline in pixels, it's a pixel per frame wide
+const SPARKLINE_HEIGHT: f32 = 40.0;
+
+const BOARD_PARAMS: [(u32, u32); 4] = [
+    (GL::TEXTURE_MIN_FILTER, GL::NEAREST),
+    (GL::TEXTURE_MAG_FILTER, GL::NEAREST),
+    (GL::TEXTURE_WRAP_S, GL::REPEAT),
+    (GL::TEXTURE_WRAP_T, GL::REPEAT),
+];
+
+render_pipeline!(LifePipeline, "shaders/life.frag");
+
+render_pipeline!(DisplayPipeline, "shaders/life_display.frag");
+
+// cycle detection starts out on for boards up to this size, reading back
+// anything bigger every frame costs too much to do unasked
+const DETECT_MAX_BOARD: BoardSize = BoardSize::Small;
+
+// boards bigger than this are left out of session snapshots, reading back
+// and deflating 64 MB every few seconds would stall the page
+const SESSION_MAX_BOARD: BoardSize = BoardSize::Medium;
+
+// side of the square board in cells
+#[derive(Clone, Copy, PartialEq)]
+enum BoardSize {
+    Tiny = 32,
+    Small = 128,
+    Medium = 512,
+    Large = 1024,
+    Huge = 4096,
+}
+
+impl SelectOption for BoardSize {
+    const ALL: &'static [Self] = &[
+        Self::Tiny,
+        Self::Small,
+        Self::Medium,
+        Self::Large,
+        Self::Huge,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Tiny => "32²",
+            Self::Small => "128²",
+            Self::Medium => "512²",
+            Self::Large => "1024²",
+            Self::Huge => "4096²",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct LifeSettings {
+    board_size: RwSignal<BoardSize>,
+    rule: RwSignal<Rule>,
+    rate: RwSignal<Rate>,
+    // a pattern waiting for the loop to put it on a fresh board
+    pattern: RwSignal<Option<Pattern>>,
+    // asks the loop to write the board out in this format
+    export: RwSignal<Option<PatternFormat>>,
+    tool: RwSignal<EditTool>,
+    brush: RwSignal<BrushSize>,
+    // what the stamp tool places, library and file patterns go here
+    // instead of onto a new board while it's picked
+    stamp: RwSignal<Option<Pattern>>,
+    rotation: RwSignal<Rotation>,
+    flip: RwSignal<bool>,
+    reset_view: RwSignal<bool>,
+    // runs the next generation on the cpu as well and reports any difference
+    check: RwSignal<bool>,
+    check_result: RwSignal<Option<String>>,
+    // looks for still lifes and oscillators, which reads the whole board
+    // back every frame until one's found
+    detect_cycles: RwSignal<bool>,
+    // copied out of the loop for the stats panel
+    generation: RwSignal<u64>,
+    populations: RwSignal<Vec<u32>>,
+    settled: RwSignal<Option<Settled>>,
+}
+
+impl Default for LifeSettings {
+    fn default() -> Self {
+        LifeSettings {
+            board_size: RwSignal::new(BoardSize::Tiny),
+            rule: RwSignal::new(rule::CONWAY),
+            rate: RwSignal::new(Rate::Twenty),
+            pattern: RwSignal::new(None),
+            export: RwSignal::new(None),
+            tool: RwSignal::new(EditTool::Draw),
+            brush: RwSignal::new(BrushSize::One),
+            stamp: RwSignal::new(None),
+            rotation: RwSignal::new(Rotation::None),
+            flip: RwSignal::new(false),
+            reset_view: RwSignal::new(false),
+            check: RwSignal::new(false),
+            check_result: RwSignal::new(None),
+            detect_cycles: RwSignal::new(detect_by_default(BoardSize::Tiny)),
+            generation: RwSignal::new(0),
+            populations: RwSignal::new(Vec::new()),
+            settled: RwSignal::new(None),
+        }
+    }
+}
+
+// settings saved alongside the board in a snapshot, the board size comes
+// from the board itself and sessions of big boards have no board at all
+#[derive(Serialize, Deserialize)]
+struct LifeConfig {
+    rule: String,
+}
+
 #[component]
-pub fn App() -> impl IntoView {
+pub fn App(snapshots: Snapshots) -> impl IntoView {
     let canvas_ref = NodeRef::<Canvas>::new();
+    let pointer_queue = use_pointers(canvas_ref);
+    // wheel turns waiting for the animation loop to zoom by them
+    let wheel_zooms = Rc::new(RefCell::new(Vec::new()));
+    {
+        let wheel_zooms = wheel_zooms.clone();
+        let _ = use_event_listener(canvas_ref, leptos::ev::wheel, move |evt| {
+            evt.prevent_default();
+            let (width, height) = evt
+                .current_target()
+                .and_then(|target| target.dyn_into::<web_sys::HtmlElement>().ok())
+                .map(|elem| (elem.client_width().max(1), elem.client_height().max(1)))
+                .unwrap_or((1, 1));
+            wheel_zooms.borrow_mut().push(WheelZoom {
+                position: (
+                    evt.offset_x() as f32 / width as f32,
+                    1.0 - evt.offset_y() as f32 / height as f32,
+                ),
+                factor: (-evt.delta_y() as f32 * 0.002).exp(),
+            });
+        });
+    }
+    let settings = LifeSettings::default();
+    let clock = SimClock::new();
+    let capture = Capture::new();
+    let rule_error = RwSignal::new(None::<String>);
+    let presets = rule::PRESETS
+        .iter()
+        .map(|(rule, name)| view! { <option value=*rule>{*name}</option> })
+        .collect_view();
     Effect::new(move |_| {
         if let Some(canvas) = canvas_ref.get() {
-            canvas.set_width(512);
-            canvas.set_height(512);
+            canvas.set_width(CANVAS_SIZE as u32);
+            canvas.set_height(CANVAS_SIZE as u32);
             let context = canvas
                 .get_context("webgl2")
                 .expect("get_context")
                 .expect("object")
                 .dyn_into::<WebGl2RenderingContext>()
                 .unwrap();
-            canvas_fill(context);
+            canvas_fill(
+                context,
+                pointer_queue.clone(),
+                wheel_zooms.clone(),
+                settings,
+                clock,
+                capture,
+                snapshots,
+            );
+        }
+    });
+
+    view! {
+        <canvas style:touch-action="none" node_ref=canvas_ref />
+        <div style:margin="40px">
+            <label>"Board " <EnumSelect value=settings.board_size /></label>
+            <label>
+                " Rule "
+                <input
+                    type="text"
+                    list="life-rules"
+                    prop:value=move || settings.rule.get().to_string()
+                    on:change=move |ev| match Rule::parse(&event_target_value(&ev)) {
+                        Ok(rule) => {
+                            settings.rule.set(rule);
+                            rule_error.set(None);
+                        }
+                        Err(err) => rule_error.set(Some(err.to_string())),
+                    }
+                />
+                <datalist id="life-rules">{presets}</datalist>
+            </label>
+            " "
+            {move || rule_error.get()}
+            <br />
+            <label>"Generations per second " <EnumSelect value=settings.rate /></label>
+            " "
+            <button on:click=move |_| settings.check.set(true)>
+                "Check a generation against the CPU"
+            </button>
+            " "
+            {move || settings.check_result.get()}
+        </div>
+        <div style:margin="40px">
+            <button on:click=move |_| settings.reset_view.set(true)>"Reset view"</button>
+            <label>" Tool " <EnumSelect value=settings.tool /></label>
+            <Show
+                when=move || settings.tool.get() == EditTool::Stamp
+                fallback=move || {
+                    view! { <label>" Brush " <EnumSelect value=settings.brush /></label> }
+                }
+            >
+                <label>" Rotate " <EnumSelect value=settings.rotation /></label>
+                <label>
+                    <input
+                        type="checkbox"
+                        prop:checked=move || settings.flip.get()
+                        on:change=move |ev| settings.flip.set(event_target_checked(&ev))
+                    />
+                    " Flip "
+                </label>
+                {move || {
+                    settings
+                        .stamp
+                        .with(|stamp| match stamp {
+                            Some(stamp) => {
+                                format!("Stamping {}", stamp.name.as_deref().unwrap_or("a pattern"))
+                            }
+                            None => "Pick a pattern to stamp".to_string(),
+                        })
+                }}
+            </Show>
+        </div>
+        <StatsPanel settings=settings />
+        <PatternLibrary settings=settings />
+        <Transport clock=clock />
+        <CaptureControls capture=capture />
+        <SnapshotControls snapshots=snapshots />
+    }
+}
+
+#[component]
+fn StatsPanel(settings: LifeSettings) -> impl IntoView {
+    let population = move || {
+        settings
+            .populations
+            .with(|populations| populations.last().copied())
+    };
+    let settled = move || {
+        settings.settled.get().map(|settled| match settled.period {
+            1 => format!("A still life by generation {}", settled.generation),
+            period => format!(
+                "Repeating with period {period} by generation {}",
+                settled.generation
+            ),
+        })
+    };
+
+    view! {
+        <div style:margin="40px">
+            "Generation " {move || settings.generation.get()} " Population "
+            {move || population().unwrap_or(0)} " "
+            <Sparkline values=settings.populations />
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=move || settings.detect_cycles.get()
+                    on:change=move |ev| settings.detect_cycles.set(event_target_checked(&ev))
+                />
+                " Detect cycles "
+            </label>
+            {settled}
+        </div>
+    }
+}
+
+// population over the last few hundred frames, scaled to the highest
+#[component]
+fn Sparkline(values: RwSignal<Vec<u32>>) -> impl IntoView {
+    let points = move || {
+        values.with(|values| {
+            let most = values.iter().copied().max().unwrap_or(0).max(1) as f32;
+            values
+                .iter()
+                .enumerate()
+                .map(|(x, value)| {
+                    format!("{x},{}", SPARKLINE_HEIGHT * (1.0 - *value as f32 / most))
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+    };
+
+    view! {
+        <svg
+            width=stats::SPARKLINE_LEN
+            height=SPARKLINE_HEIGHT
+            style:vertical-align="middle"
+        >
+            <polyline points=points fill="none" stroke="currentColor" />
+        </svg>
+    }
+}
+
+#[component]
+fn PatternLibrary(settings: LifeSettings) -> impl IntoView {
+    let error = RwSignal::new(None::<String>);
+    let largest = *BoardSize::ALL.last().unwrap() as usize;
+    let load = move |text: &str| match Pattern::parse(text) {
+        Ok(pattern) if pattern.width.max(pattern.height) > largest => {
+            error.set(Some(format!(
+                "patterns can be at most {largest} cells across"
+            )));
+        }
+        Ok(pattern) => {
+            if settings.tool.get_untracked() == EditTool::Stamp {
+                settings.stamp.set(Some(pattern));
+            } else {
+                settings.pattern.set(Some(pattern));
+            }
+            error.set(None);
+        }
+        Err(err) => error.set(Some(err.to_string())),
+    };
+
+    let file = RwSignal::new(None::<Vec<u8>>);
+    Effect::new(move |_| {
+        if let Some(bytes) = file.get() {
+            load(&String::from_utf8_lossy(&bytes));
         }
     });
 
-    view! { <canvas node_ref=canvas_ref /> }
+    let library = pattern::LIBRARY
+        .iter()
+        .map(|(name, rle)| view! { <button on:click=move |_| load(rle)>{*name}</button> })
+        .collect_view();
+
+    view! {
+        <div style:margin="40px">
+            "Patterns " {library} <br />
+            <label>
+                "Open "
+                <input
+                    type="file"
+                    accept=".rle,.cells,.txt"
+                    on:change=move |ev| {
+                        let input = event_target::<HtmlInputElement>(&ev);
+                        if let Some(picked) = input.files().and_then(|files| files.get(0)) {
+                            read_file(picked, file);
+                        }
+                    }
+                />
+            </label>
+            <button on:click=move |_| settings.export.set(Some(PatternFormat::Rle))>
+                "Export RLE"
+            </button>
+            <button
+                on:click=move |_| settings.export.set(Some(PatternFormat::Plaintext))
+                disabled=move || { settings.rule.get().states > 2 }
+            >
+                "Export plaintext"
+            </button>
+            " "
+            {move || error.get()}
+        </div>
+    }
 }
 
-fn canvas_fill(context: WebGl2RenderingContext) {
+fn canvas_fill(
+    context: WebGl2RenderingContext,
+    pointer_queue: PointerQueue,
+    wheel_zooms: Rc<RefCell<Vec<WheelZoom>>>,
+    settings: LifeSettings,
+    clock: SimClock,
+    capture: Capture,
+    snapshots: Snapshots,
+) {
+    // the population count sums into float textures
+    context.get_extension("EXT_color_buffer_float").unwrap();
     let quad_vert_shader = compile_shader(
         &context,
         GL::VERTEX_SHADER,
@@ -36,10 +422,10 @@ fn canvas_fill(context: WebGl2RenderingContext) {
     )
     .unwrap();
 
-    let quad_frag_shader = compile_shader(
+    let display_frag_shader = compile_shader(
         &context,
         GL::FRAGMENT_SHADER,
-        include_str!("shaders/quad.frag"),
+        include_str!("shaders/life_display.frag"),
     )
     .unwrap();
 
@@ -49,89 +435,377 @@ fn canvas_fill(context: WebGl2RenderingContext) {
         include_str!("shaders/life.frag"),
     )
     .unwrap();
-    let quad_program = Program::create(&context, &quad_vert_shader, &quad_frag_shader);
+    let display_program = Program::create(&context, &quad_vert_shader, &display_frag_shader);
     let life_program = Program::create(&context, &quad_vert_shader, &life_frag_shader);
 
-    let mut game_board = make_game_board(&context);
-
-    let (frame_count, set_frame_count) = signal(());
+    let mut display_pipeline = DisplayPipeline::create(&context, display_program);
+    let mut life_pipeline = LifePipeline::create(&context, life_program);
+    let mut camera = Camera::new();
+    let mut editor = Editor::create(&context, &quad_vert_shader);
+    let mut population = Population::create(&context, &quad_vert_shader);
+    let mut stats = Stats::new();
+    // set whenever the board changes other than by a generation, the stats
+    // start over from whatever's on it
+    let mut stats_stale = true;
+    let mut stats_rule = settings.rule.get_untracked();
 
-    request_animation_frame(move || {
-        *set_frame_count.write();
-    });
+    // new boards start out with a glider from the library
+    let glider = Pattern::parse(pattern::LIBRARY[0].1).unwrap();
+    let mut board_size = settings.board_size.get_untracked();
+    let mut game_board = make_game_board(&context, board_size as usize, &glider);
 
-    let mut prev_time = None::<f64>;
+    let mut pacer = Pacer::new(settings.rate.get_untracked());
+    let mut recorder = Recorder::new(capture, "life");
 
     let quad = Quad::create(&context);
 
-    Effect::new(move || {
-        frame_count.read();
-        let now = window().performance().unwrap().now();
-        if !prev_time.is_some() || now - prev_time.unwrap() > 50.0 {
-            prev_time = Some(now);
+    own_loop(AnimationLoop::start(move |frame| {
+        if let Some(bytes) = snapshots.take_load() {
+            match Snapshot::decode(&bytes)
+                .map_err(|err| err.to_string())
+                .and_then(|snapshot| load_board(&context, &snapshot))
+            {
+                Ok((board, rule)) => {
+                    if let Some((board, size)) = board {
+                        game_board = board;
+                        board_size = size;
+                        settings.board_size.set(size);
+                        settings.detect_cycles.set(detect_by_default(size));
+                        camera = Camera::new();
+                    }
+                    settings.rule.set(rule);
+                    stats_stale = true;
+                }
+                Err(err) => console::log_1(&format!("Failed to load snapshot: {err}").into()),
+            }
+        }
 
-            context.use_program(Some(quad_program.program()));
-            context.uniform1i(
-                quad_program.uniforms().get("u_texture"),
-                game_board.read().attach(0),
-            );
-            quad.blit(None);
+        if settings.board_size.get_untracked() != board_size {
+            board_size = settings.board_size.get_untracked();
+            game_board = make_game_board(&context, board_size as usize, &glider);
+            settings.detect_cycles.set(detect_by_default(board_size));
+            // the old zoom could be past what the new size allows
+            camera = Camera::new();
+            stats_stale = true;
+        }
 
-            context.use_program(Some(life_program.program()));
-            context.uniform1i(
-                life_program.uniforms().get("u_texture"),
-                game_board.read().attach(0),
-            );
-            context.uniform2f(
-                life_program.uniforms().get("u_texel_size"),
-                game_board.read().texel_size().0,
-                game_board.read().texel_size().1,
+        if let Some(pattern) = settings.pattern.write_untracked().take() {
+            // grow the board if the pattern doesn't fit
+            let fits = |size: &BoardSize| *size as usize >= pattern.width.max(pattern.height);
+            if !fits(&board_size) {
+                board_size = *BoardSize::ALL.iter().find(|size| fits(size)).unwrap();
+                settings.board_size.set(board_size);
+                settings.detect_cycles.set(detect_by_default(board_size));
+            }
+            if let Some(rule) = pattern.rule {
+                settings.rule.set(rule);
+            }
+            game_board = make_game_board(&context, board_size as usize, &pattern);
+            camera = Camera::new();
+            stats_stale = true;
+        }
+
+        if std::mem::take(&mut *settings.reset_view.write_untracked()) {
+            camera = Camera::new();
+        }
+        for zoom in wheel_zooms.borrow_mut().drain(..) {
+            camera.zoom_at(zoom, board_size as usize, CANVAS_SIZE);
+        }
+
+        // edits land before the generation so they show up even when paused
+        let inputs = std::mem::take(&mut *pointer_queue.borrow_mut());
+        if !inputs.is_empty() {
+            settings.stamp.with_untracked(|stamp| {
+                let options = EditOptions {
+                    tool: settings.tool.get_untracked(),
+                    brush: settings.brush.get_untracked(),
+                    stamp: stamp.as_ref(),
+                    rotation: settings.rotation.get_untracked(),
+                    flip: settings.flip.get_untracked(),
+                };
+                for mut input in inputs {
+                    if options.tool == EditTool::Pan {
+                        camera.pan(input);
+                    } else {
+                        input.position = camera.to_board(input.position);
+                        stats_stale |=
+                            editor.handle(&context, &quad, &mut game_board, input, &options);
+                    }
+                }
+            });
+        }
+
+        let rate = settings.rate.get_untracked();
+        // recordings take one generation per frame so each frame stands
+        // for the same time
+        let generations = if capture.is_recording() {
+            1
+        } else {
+            pacer.generations(rate, clock.advance(frame.dt), frame.dt) + clock.take_step() as u32
+        };
+        let rule = settings.rule.get_untracked();
+        if rule != stats_rule {
+            stats_rule = rule;
+            stats_stale = true;
+        }
+        // generations since the stats were last recorded, if they need it.
+        // they're only recorded once a frame however many generations ran
+        let mut advanced = None;
+        if std::mem::take(&mut stats_stale) {
+            stats = Stats::new();
+            advanced = Some(0);
+        }
+        if std::mem::take(&mut *settings.check.write_untracked()) {
+            let size = board_size as usize;
+            let before = reference::states(&game_board.read().read_rgba_u8());
+            let expected = reference::step(&rule, size, size, &before);
+            run_generation(&context, &quad, &mut life_pipeline, &mut game_board, &rule);
+            advanced = Some(advanced.unwrap_or(0) + 1);
+            let after = reference::states(&game_board.read().read_rgba_u8());
+            let wrong = after
+                .iter()
+                .zip(&expected)
+                .filter(|(gpu, cpu)| gpu != cpu)
+                .count();
+            settings.check_result.set(Some(match wrong {
+                0 => "GPU and CPU agree".to_string(),
+                wrong => format!("{wrong} cells differ from the CPU"),
+            }));
+        }
+        for _ in 0..generations {
+            run_generation(&context, &quad, &mut life_pipeline, &mut game_board, &rule);
+        }
+        if generations > 0 {
+            advanced = Some(advanced.unwrap_or(0) + generations as u64);
+        }
+        if let Some(advanced) = advanced {
+            let detect = settings.detect_cycles.get_untracked().then_some(&rule);
+            let generation = stats.generation() + advanced;
+            record_stats(
+                &context,
+                &quad,
+                &mut population,
+                &mut stats,
+                &game_board,
+                generation,
+                detect,
             );
-            quad.blit(Some(&game_board.write()));
+            settings.generation.set(stats.generation());
+            settings
+                .populations
+                .set(stats.populations().iter().copied().collect());
+            settings.settled.set(stats.settled());
+        }
 
-            game_board.swap();
+        // redrawn every frame so there's always something to capture
+        display_pipeline.set_arguments(
+            &context,
+            game_board.read(),
+            (board_size as u32 as f32, board_size as u32 as f32),
+            camera.center(),
+            (
+                camera.zoom(),
+                camera.cell_pixels(board_size as usize, CANVAS_SIZE),
+            ),
+            rule.states as f32,
+        );
+        quad.blit(None);
+
+        recorder.capture(&context, rate.step_ms());
+
+        if snapshots.take_save() {
+            snapshots.save(&save_board(&game_board, settings, true), "life");
         }
-        request_animation_frame(move || {
-            *set_frame_count.write();
-        });
-    });
+        if snapshots.session_due() {
+            let with_board = board_size as usize <= SESSION_MAX_BOARD as usize;
+            snapshots.store_session(&save_board(&game_board, settings, with_board));
+        }
+        if let Some(format) = settings.export.write_untracked().take() {
+            let mut pattern = read_pattern(&game_board).trimmed();
+            pattern.rule = Some(settings.rule.get_untracked());
+            download(
+                pattern.write(format).as_bytes(),
+                &format!("life.{}", format.extension()),
+                "text/plain",
+            );
+        }
+    }));
 }
 
-fn make_game_board(context: &WebGl2RenderingContext) -> SwappableTexture {
-    let mut texture_data: [u8; 4096] = [0; 4096];
+fn run_generation(
+    context: &WebGl2RenderingContext,
+    quad: &Quad,
+    pipeline: &mut LifePipeline,
+    board: &mut SwappableTexture,
+    rule: &Rule,
+) {
+    // each count is either a mask or a range, the other one is left empty
+    let split = |counts| match counts {
+        Counts::Mask(mask) => (mask as f32, (1.0, 0.0)),
+        Counts::Range(min, max) => (0.0, (min as f32, max as f32)),
+    };
+    let (birth, birth_range) = split(rule.birth);
+    let (survival, survival_range) = split(rule.survival);
+    let shape = match rule.neighbourhood {
+        Neighbourhood::Moore => 0.0,
+        Neighbourhood::VonNeumann => 1.0,
+        Neighbourhood::Circular => 2.0,
+    };
+    pipeline.set_arguments(
+        context,
+        board.read(),
+        board.read().texel_size(),
+        (birth, survival),
+        (
+            birth_range.0,
+            birth_range.1,
+            survival_range.0,
+            survival_range.1,
+        ),
+        (
+            rule.radius as f32,
+            shape,
+            rule.middle as u32 as f32,
+            rule.states as f32,
+        ),
+    );
+    quad.blit(Some(board.write()));
+    board.swap();
+}
 
-    for (i, elem) in texture_data.iter_mut().enumerate() {
-        *elem = (i % 4 == 3) as u8 * 255;
+// counts the board's population, and hashes it when detect has the rule to
+// look for cycles under. There's no need once one's been found
+fn record_stats(
+    context: &WebGl2RenderingContext,
+    quad: &Quad,
+    population: &mut Population,
+    stats: &mut Stats,
+    board: &SwappableTexture,
+    generation: u64,
+    detect: Option<&Rule>,
+) {
+    let count = population.count(context, quad, board.read());
+    let Some(rule) = detect.filter(|_| stats.settled().is_none()) else {
+        stats.record(generation, count, None);
+        return;
+    };
+    let states = reference::states(&board.read().read_rgba_u8());
+    stats.record(generation, count, Some(stats::hash_states(&states)));
+    // with generations going by between records the repeat found can be a
+    // multiple of the period, the cpu steps round the cycle once to find it
+    // when that fits the budget, otherwise the multiple stands
+    if let Some(settled) = stats.settled()
+        && settled.period > 1
+    {
+        let size = board.read().width() as usize;
+        let most = settled
+            .period
+            .min(stats::MAX_PERIOD as u64)
+            .min(reference::affordable_generations(rule, size, size));
+        if most > 0
+            && let Some(period) = reference::period(rule, size, size, &states, most)
+        {
+            stats.set_period(period);
+        }
     }
+}
 
-    for elem in texture_data[1332..1344].iter_mut() {
-        *elem = 255;
+fn detect_by_default(size: BoardSize) -> bool {
+    size as usize <= DETECT_MAX_BOARD as usize
+}
+
+fn save_board(board: &SwappableTexture, settings: LifeSettings, with_board: bool) -> Snapshot {
+    let config = LifeConfig {
+        rule: settings.rule.get_untracked().to_string(),
+    };
+    let textures = with_board.then(|| {
+        TextureData::from_rgba_u8(
+            "board",
+            board.read().width(),
+            board.read().height(),
+            TextureFormat::Rgba8,
+            &board.read().read_rgba_u8(),
+        )
+    });
+    Snapshot {
+        kind: DemoKind::Life,
+        config: serde_json::to_string(&config).expect("failed to write config"),
+        textures: textures.into_iter().collect(),
     }
+}
 
-    for elem in texture_data[1212..1216].iter_mut() {
-        *elem = 255;
+fn load_board(
+    context: &WebGl2RenderingContext,
+    snapshot: &Snapshot,
+) -> Result<(Option<(SwappableTexture, BoardSize)>, Rule), String> {
+    if snapshot.kind != DemoKind::Life {
+        return Err("snapshot is from a different demo".to_string());
+    }
+    let config = snapshot
+        .config::<LifeConfig>()
+        .map_err(|err| err.to_string())?;
+    let rule = Rule::parse(&config.rule).map_err(|err| err.to_string())?;
+    // a session of a big board, only the rule comes back
+    let Some(board) = snapshot.texture("board") else {
+        return Ok((None, rule));
+    };
+    if board.format != TextureFormat::Rgba8 || board.width != board.height {
+        return Err("board isn't square".to_string());
     }
+    let size = BoardSize::ALL
+        .iter()
+        .find(|size| **size as u32 == board.width)
+        .ok_or("board isn't a supported size")?;
+    Ok((
+        Some((board.create_texture(context, &BOARD_PARAMS), *size)),
+        rule,
+    ))
+}
 
-    for elem in texture_data[1080..1084].iter_mut() {
-        *elem = 255;
+// the whole board as a pattern, dying cells and all
+fn read_pattern(board: &SwappableTexture) -> Pattern {
+    let width = board.read().width() as usize;
+    let height = board.read().height() as usize;
+    let states = reference::states(&board.read().read_rgba_u8());
+    // board rows run from the bottom, pattern rows from the top
+    let cells = states
+        .chunks_exact(width)
+        .rev()
+        .flatten()
+        .copied()
+        .collect();
+    Pattern::from_cells(width, height, cells)
+}
+
+// an empty board with pattern in the middle, which has to fit
+fn make_game_board(
+    context: &WebGl2RenderingContext,
+    size: usize,
+    pattern: &Pattern,
+) -> SwappableTexture {
+    let mut texture_data = [0, 0, 0, 255].repeat(size * size);
+
+    let origin_x = (size - pattern.width) / 2;
+    let origin_y = (size - pattern.height) / 2;
+    for y in 0..pattern.height {
+        for x in (0..pattern.width).filter(|&x| pattern.state(x, y) != 0) {
+            let row = origin_y + pattern.height - 1 - y;
+            let idx = (row * size + origin_x + x) * 4;
+            texture_data[idx..idx + 4].copy_from_slice(&reference::texel(pattern.state(x, y)));
+        }
     }
 
-    return SwappableTexture::create(
+    SwappableTexture::create(
         context,
         GL::TEXTURE_2D,
         0,
         GL::RGBA,
-        32,
-        32,
+        size as i32,
+        size as i32,
         0,
         GL::RGBA,
         GL::UNSIGNED_BYTE,
         Some(ArrayView::create(&texture_data)),
-        &[
-            (GL::TEXTURE_MIN_FILTER, GL::NEAREST),
-            (GL::TEXTURE_MAG_FILTER, GL::NEAREST),
-            (GL::TEXTURE_WRAP_S, GL::REPEAT),
-            (GL::TEXTURE_WRAP_T, GL::REPEAT),
-        ],
-    );
+        &BOARD_PARAMS,
+    )
 }
@@ -0,0 +1,88 @@
+use crate::pointers::{PointerInput, PointerPhase};
+
+// closest the camera gets, in canvas pixels per cell
+const MAX_CELL_PIXELS: f32 = 64.0;
+
+// a wheel turn over the canvas, factor above one zooms in
+#[derive(Clone, Copy)]
+pub struct WheelZoom {
+    pub position: (f32, f32),
+    pub factor: f32,
+}
+
+// What part of the board the canvas shows. Positions on the canvas are uv
+// with (0, 0) at the bottom left, the board repeats in every direction so
+// the camera can pan forever
+pub struct Camera {
+    // board uv at the middle of the canvas
+    center: (f32, f32),
+    // 1 fits the whole board on the canvas
+    zoom: f32,
+    // the pointer dragging the view and where it was last
+    drag: Option<(i32, (f32, f32))>,
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Camera {
+            center: (0.5, 0.5),
+            zoom: 1.0,
+            drag: None,
+        }
+    }
+
+    pub fn center(&self) -> (f32, f32) {
+        self.center
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    // board uv under a canvas position, always inside [0, 1)
+    pub fn to_board(&self, canvas: (f32, f32)) -> (f32, f32) {
+        (
+            (self.center.0 + (canvas.0 - 0.5) / self.zoom).rem_euclid(1.0),
+            (self.center.1 + (canvas.1 - 0.5) / self.zoom).rem_euclid(1.0),
+        )
+    }
+
+    pub fn cell_pixels(&self, board_size: usize, canvas_size: usize) -> f32 {
+        self.zoom * canvas_size as f32 / board_size as f32
+    }
+
+    // zooms about position so the cell under it stays put
+    pub fn zoom_at(&mut self, zoom: WheelZoom, board_size: usize, canvas_size: usize) {
+        let max_zoom = (MAX_CELL_PIXELS * board_size as f32 / canvas_size as f32).max(1.0);
+        let new_zoom = (self.zoom * zoom.factor).clamp(1.0, max_zoom);
+        let offset = (zoom.position.0 - 0.5, zoom.position.1 - 0.5);
+        self.center = (
+            (self.center.0 + offset.0 / self.zoom - offset.0 / new_zoom).rem_euclid(1.0),
+            (self.center.1 + offset.1 / self.zoom - offset.1 / new_zoom).rem_euclid(1.0),
+        );
+        self.zoom = new_zoom;
+    }
+
+    // drags the board along with the pointer
+    pub fn pan(&mut self, input: PointerInput) {
+        match input.phase {
+            PointerPhase::Down => self.drag = Some((input.id, input.position)),
+            PointerPhase::Move => {
+                if let Some((id, last)) = self.drag.as_mut()
+                    && *id == input.id
+                {
+                    self.center = (
+                        (self.center.0 - (input.position.0 - last.0) / self.zoom).rem_euclid(1.0),
+                        (self.center.1 - (input.position.1 - last.1) / self.zoom).rem_euclid(1.0),
+                    );
+                    *last = input.position;
+                }
+            }
+            PointerPhase::Up => {
+                if self.drag.is_some_and(|(id, _)| id == input.id) {
+                    self.drag = None;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::BTreeMap;
+
+use utility::prelude::*;
+
+use web_sys::{WebGl2RenderingContext, WebGlShader};
+
+use super::pattern::Pattern;
+use super::reference;
+use crate::controls::SelectOption;
+use crate::pointers::{PointerInput, PointerPhase};
+
+type GL = WebGl2RenderingContext;
+
+render_pipeline!(BrushPipeline, "../shaders/brush.frag");
+
+render_pipeline!(StampPipeline, "../shaders/stamp.frag");
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum EditTool {
+    // draws live cells, or erases when the stroke starts on a live one
+    Draw,
+    Erase,
+    Stamp,
+    // drags the view around instead of editing
+    Pan,
+}
+
+impl SelectOption for EditTool {
+    const ALL: &'static [Self] = &[Self::Draw, Self::Erase, Self::Stamp, Self::Pan];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Draw => "Draw",
+            Self::Erase => "Erase",
+            Self::Stamp => "Stamp",
+            Self::Pan => "Pan",
+        }
+    }
+}
+
+// brush width in cells
+#[derive(Clone, Copy, PartialEq)]
+pub enum BrushSize {
+    One = 1,
+    Three = 3,
+    Five = 5,
+    Nine = 9,
+}
+
+impl SelectOption for BrushSize {
+    const ALL: &'static [Self] = &[Self::One, Self::Three, Self::Five, Self::Nine];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::One => "1",
+            Self::Three => "3",
+            Self::Five => "5",
+            Self::Nine => "9",
+        }
+    }
+}
+
+// clockwise quarter turns applied to the stamp
+#[derive(Clone, Copy, PartialEq)]
+pub enum Rotation {
+    None = 0,
+    Quarter = 1,
+    Half = 2,
+    ThreeQuarters = 3,
+}
+
+impl SelectOption for Rotation {
+    const ALL: &'static [Self] = &[Self::None, Self::Quarter, Self::Half, Self::ThreeQuarters];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::None => "0°",
+            Self::Quarter => "90°",
+            Self::Half => "180°",
+            Self::ThreeQuarters => "270°",
+        }
+    }
+}
+
+// what the tools do with a single pointer event
+pub struct EditOptions<'a> {
+    pub tool: EditTool,
+    pub brush: BrushSize,
+    pub stamp: Option<&'a Pattern>,
+    pub rotation: Rotation,
+    pub flip: bool,
+}
+
+struct Stroke {
+    // the last cell the stroke reached
+    cell: (f32, f32),
+    value: f32,
+}
+
+// a straight line of the brush between two cells, setting them to value
+struct Segment {
+    from: (f32, f32),
+    to: (f32, f32),
+    value: f32,
+    brush: BrushSize,
+}
+
+// Applies pointer edits to the board on the gpu so editing works while it
+// runs, the only readback is the cell under a new draw stroke
+pub struct Editor {
+    brush_pipeline: BrushPipeline,
+    stamp_pipeline: StampPipeline,
+    strokes: BTreeMap<i32, Stroke>,
+}
+
+impl Editor {
+    pub fn create(context: &WebGl2RenderingContext, quad_vert_shader: &WebGlShader) -> Self {
+        let brush_frag_shader = compile_shader(
+            context,
+            GL::FRAGMENT_SHADER,
+            include_str!("../shaders/brush.frag"),
+        )
+        .unwrap();
+
+        let stamp_frag_shader = compile_shader(
+            context,
+            GL::FRAGMENT_SHADER,
+            include_str!("../shaders/stamp.frag"),
+        )
+        .unwrap();
+
+        let brush_program = Program::create(context, quad_vert_shader, &brush_frag_shader);
+        let stamp_program = Program::create(context, quad_vert_shader, &stamp_frag_shader);
+
+        Editor {
+            brush_pipeline: BrushPipeline::create(context, brush_program),
+            stamp_pipeline: StampPipeline::create(context, stamp_program),
+            strokes: BTreeMap::new(),
+        }
+    }
+
+    // input positions are uv on the board rather than the canvas, true when
+    // the input changed the board. Moves with no stroke going are just hovering
+    pub fn handle(
+        &mut self,
+        context: &WebGl2RenderingContext,
+        quad: &Quad,
+        board: &mut SwappableTexture,
+        input: PointerInput,
+        options: &EditOptions,
+    ) -> bool {
+        let size = (board.read().width(), board.read().height());
+        let cell = (
+            (input.position.0 * size.0 as f32)
+                .clamp(0.0, size.0 as f32 - 1.0)
+                .floor(),
+            (input.position.1 * size.1 as f32)
+                .clamp(0.0, size.1 as f32 - 1.0)
+                .floor(),
+        );
+
+        match input.phase {
+            PointerPhase::Down => match options.tool {
+                EditTool::Stamp => {
+                    let Some(stamp) = options.stamp else {
+                        return false;
+                    };
+                    let mut stamp = stamp.rotated(options.rotation as u32);
+                    if options.flip {
+                        stamp = stamp.flipped();
+                    }
+                    self.stamp(context, quad, board, &stamp, cell);
+                    true
+                }
+                EditTool::Pan => false,
+                EditTool::Draw | EditTool::Erase => {
+                    let value = if options.tool == EditTool::Erase
+                        || board
+                            .read()
+                            .read_region_u8(cell.0 as i32, cell.1 as i32, 1, 1)[0]
+                            > 0
+                    {
+                        0.0
+                    } else {
+                        1.0
+                    };
+                    self.strokes.insert(input.id, Stroke { cell, value });
+                    let segment = Segment {
+                        from: cell,
+                        to: cell,
+                        value,
+                        brush: options.brush,
+                    };
+                    self.brush(context, quad, board, segment);
+                    true
+                }
+            },
+            PointerPhase::Move => {
+                let Some(stroke) = self.strokes.get_mut(&input.id) else {
+                    return false;
+                };
+                // a stroke that wrapped around an edge starts over on the
+                // other side instead of cutting across the board
+                let wrapped = (stroke.cell.0 - cell.0).abs() > size.0 as f32 / 2.0
+                    || (stroke.cell.1 - cell.1).abs() > size.1 as f32 / 2.0;
+                let segment = Segment {
+                    from: if wrapped { cell } else { stroke.cell },
+                    to: cell,
+                    value: stroke.value,
+                    brush: options.brush,
+                };
+                stroke.cell = cell;
+                self.brush(context, quad, board, segment);
+                true
+            }
+            PointerPhase::Up => {
+                self.strokes.remove(&input.id);
+                false
+            }
+        }
+    }
+
+    fn brush(
+        &mut self,
+        context: &WebGl2RenderingContext,
+        quad: &Quad,
+        board: &mut SwappableTexture,
+        segment: Segment,
+    ) {
+        let size = (board.read().width() as f32, board.read().height() as f32);
+        let Segment {
+            from,
+            to,
+            value,
+            brush,
+        } = segment;
+        // aim at cell centres
+        self.brush_pipeline.set_arguments(
+            context,
+            board.read(),
+            size,
+            (from.0 + 0.5, from.1 + 0.5, to.0 + 0.5, to.1 + 0.5),
+            brush as u32 as f32 / 2.0,
+            value,
+        );
+        quad.blit(Some(board.write()));
+        board.swap();
+    }
+
+    // centres the stamp on cell
+    fn stamp(
+        &mut self,
+        context: &WebGl2RenderingContext,
+        quad: &Quad,
+        board: &mut SwappableTexture,
+        stamp: &Pattern,
+        cell: (f32, f32),
+    ) {
+        if stamp.width == 0 || stamp.height == 0 {
+            return;
+        }
+        // the pattern's rows run from the top, the texture's from the bottom
+        let pixels: Vec<u8> = (0..stamp.height)
+            .rev()
+            .flat_map(|y| (0..stamp.width).map(move |x| (x, y)))
+            .flat_map(|(x, y)| reference::texel(stamp.state(x, y)))
+            .collect();
+        let stamp_texture = BufferedTexture::create(
+            context,
+            GL::TEXTURE_2D,
+            0,
+            GL::RGBA,
+            stamp.width as i32,
+            stamp.height as i32,
+            0,
+            GL::RGBA,
+            GL::UNSIGNED_BYTE,
+            Some(ArrayView::create(&pixels)),
+            &[
+                (GL::TEXTURE_MIN_FILTER, GL::NEAREST),
+                (GL::TEXTURE_MAG_FILTER, GL::NEAREST),
+                (GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE),
+                (GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE),
+            ],
+        );
+
+        let size = (board.read().width() as f32, board.read().height() as f32);
+        let origin = (
+            cell.0 - (stamp.width / 2) as f32,
+            cell.1 - (stamp.height / 2) as f32,
+        );
+        self.stamp_pipeline.set_arguments(
+            context,
+            board.read(),
+            &stamp_texture,
+            size,
+            origin,
+            (stamp.width as f32, stamp.height as f32),
+        );
+        quad.blit(Some(board.write()));
+        board.swap();
+    }
+}
@@ -0,0 +1,685 @@
+use std::fmt::{self, Write};
+
+use super::rule::{Rule, RuleError};
+
+// RLE lines are kept to this many characters when writing
+const RLE_LINE: usize = 70;
+
+// sides bigger than the largest board are turned away before anything's
+// allocated for them
+const MAX_SIDE: usize = 4096;
+
+// multi-state RLE names states 1 to 24 A to X, and each 24 after that gets
+// a prefix from p to y
+const STATE_LETTERS: u8 = 24;
+
+// classic patterns offered in the library panel, as RLE
+pub const LIBRARY: &[(&str, &str)] = &[
+    (
+        "Glider",
+        "#N Glider\nx = 3, y = 3, rule = B3/S23\n3o$2bo$bo!",
+    ),
+    (
+        "Lightweight spaceship",
+        "#N Lightweight spaceship\nx = 5, y = 4, rule = B3/S23\nbo2bo$o4b$o3bo$4o!",
+    ),
+    (
+        "Pulsar",
+        "#N Pulsar\n#C A period 3 oscillator.\nx = 13, y = 13, rule = B3/S23\n\
+         2b3o3b3o2b2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2b2$2b3o3b3o2b$\
+         o4bobo4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!",
+    ),
+    (
+        "Gosper glider gun",
+        "#N Gosper glider gun\n#C The first known gun, a glider every 30 generations.\n\
+         x = 36, y = 9, rule = B3/S23\n\
+         24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$\
+         2o8bo3bob2o4bobo$10bo5bo7bo$11bo3bo$12b2o!",
+    ),
+    (
+        "Acorn",
+        "#N Acorn\n#C A methuselah that takes 5206 generations to settle.\n\
+         x = 7, y = 3, rule = B3/S23\nbo5b$3bo3b$2o2b3o!",
+    ),
+    (
+        "R-pentomino",
+        "#N R-pentomino\n#C A methuselah that takes 1103 generations to settle.\n\
+         x = 3, y = 3, rule = B3/S23\nb2o$2ob$bo!",
+    ),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PatternFormat {
+    Rle,
+    Plaintext,
+}
+
+impl PatternFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Rle => "rle",
+            Self::Plaintext => "cells",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PatternError {
+    MissingHeader,
+    BadHeader(String),
+    BadTag(char),
+    Rule(RuleError),
+    // the cells go past the size the header gave
+    TooBig,
+    // the header asks for more than MAX_SIDE
+    TooLarge(usize, usize),
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHeader => write!(f, "no x = .., y = .. line"),
+            Self::BadHeader(line) => write!(f, "can't read header {line}"),
+            Self::BadTag(tag) => write!(f, "unexpected {tag}"),
+            Self::Rule(err) => write!(f, "bad rule: {err}"),
+            Self::TooBig => write!(f, "cells go past the size in the header"),
+            Self::TooLarge(width, height) => {
+                write!(f, "{width}x{height} is bigger than {MAX_SIDE}x{MAX_SIDE}")
+            }
+        }
+    }
+}
+
+// A rectangle of cells with whatever metadata its file had, rows run from
+// the top like they do in both file formats. Cells hold their state, 0 is
+// dead, 1 alive and anything higher dying like on the board
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Pattern {
+    pub name: Option<String>,
+    pub comments: Vec<String>,
+    pub rule: Option<Rule>,
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<u8>,
+}
+
+impl Pattern {
+    pub fn from_cells(width: usize, height: usize, cells: Vec<u8>) -> Self {
+        assert_eq!(cells.len(), width * height);
+        Pattern {
+            width,
+            height,
+            cells,
+            ..Default::default()
+        }
+    }
+
+    pub fn state(&self, x: usize, y: usize) -> u8 {
+        self.cells[y * self.width + x]
+    }
+
+    // picks the format from the text, RLE has a header line and plaintext
+    // doesn't
+    pub fn parse(text: &str) -> Result<Self, PatternError> {
+        let is_rle = text
+            .lines()
+            .map(str::trim)
+            .any(|line| line.starts_with('x') && line.contains('='));
+        if is_rle {
+            Self::parse_rle(text)
+        } else {
+            Self::parse_plaintext(text)
+        }
+    }
+
+    pub fn parse_rle(text: &str) -> Result<Self, PatternError> {
+        let mut pattern = Pattern::default();
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+
+        let header = loop {
+            let line = lines.next().ok_or(PatternError::MissingHeader)?;
+            if let Some(comment) = line.strip_prefix('#') {
+                pattern.read_comment(comment);
+            } else {
+                break line;
+            }
+        };
+        // Larger than Life rules have commas of their own, so everything
+        // after rule = belongs to the rule
+        let (fields, rule) = match header.find("rule") {
+            Some(at) => (&header[..at], Some(&header[at..])),
+            None => (header, None),
+        };
+        let bad_header = || PatternError::BadHeader(header.to_string());
+        for field in fields.split(',').filter(|field| !field.trim().is_empty()) {
+            let (key, value) = field.split_once('=').ok_or_else(bad_header)?;
+            let size = || value.trim().parse::<usize>().map_err(|_| bad_header());
+            match key.trim() {
+                "x" => pattern.width = size()?,
+                "y" => pattern.height = size()?,
+                _ => {}
+            }
+        }
+        if let Some(rule) = rule {
+            let (_, value) = rule.split_once('=').ok_or_else(bad_header)?;
+            pattern.rule = Some(Rule::parse(value).map_err(PatternError::Rule)?);
+        }
+        if pattern.width.max(pattern.height) > MAX_SIDE {
+            return Err(PatternError::TooLarge(pattern.width, pattern.height));
+        }
+        pattern.cells = vec![0; pattern.width * pattern.height];
+
+        let (mut x, mut y): (usize, usize) = (0, 0);
+        let mut count: usize = 0;
+        // the p to y before a multi-state letter, 0 for none
+        let mut prefix: u8 = 0;
+        'body: for line in lines {
+            for tag in line.chars() {
+                if let Some(digit) = tag.to_digit(10) {
+                    count = count
+                        .checked_mul(10)
+                        .and_then(|count| count.checked_add(digit as usize))
+                        .ok_or(PatternError::TooBig)?;
+                    continue;
+                }
+                if prefix == 0 && ('p'..='y').contains(&tag) {
+                    prefix = tag as u8 - b'p' + 1;
+                    continue;
+                }
+                let run = count.max(1);
+                count = 0;
+                let state = match tag {
+                    'o' if prefix == 0 => Some(1),
+                    'A'..='X' => {
+                        let state =
+                            prefix as u32 * STATE_LETTERS as u32 + (tag as u32 - 'A' as u32) + 1;
+                        Some(u8::try_from(state).map_err(|_| PatternError::BadTag(tag))?)
+                    }
+                    _ if prefix != 0 => return Err(PatternError::BadTag(tag)),
+                    _ => None,
+                };
+                prefix = 0;
+                match (tag, state) {
+                    (_, Some(state)) => {
+                        let end = x.checked_add(run);
+                        if end.is_none_or(|end| end > pattern.width) || y >= pattern.height {
+                            return Err(PatternError::TooBig);
+                        }
+                        let start = y * pattern.width + x;
+                        pattern.cells[start..start + run].fill(state);
+                        x += run;
+                    }
+                    ('!', _) => break 'body,
+                    ('$', _) => (x, y) = (0, y.saturating_add(run)),
+                    ('b' | '.', _) => x = x.saturating_add(run),
+                    (tag, _) if tag.is_whitespace() => {}
+                    (tag, _) => return Err(PatternError::BadTag(tag)),
+                }
+            }
+        }
+        Ok(pattern)
+    }
+
+    pub fn parse_plaintext(text: &str) -> Result<Self, PatternError> {
+        let mut pattern = Pattern::default();
+        let mut rows = Vec::new();
+        for line in text.lines() {
+            if let Some(comment) = line.strip_prefix('!') {
+                match comment.strip_prefix("Name:") {
+                    Some(name) => pattern.name = Some(name.trim().to_string()),
+                    None => pattern.comments.push(comment.trim().to_string()),
+                }
+                continue;
+            }
+            let row = line
+                .trim_end()
+                .chars()
+                .map(|cell| match cell {
+                    '.' => Ok(0),
+                    'O' | '*' => Ok(1),
+                    cell => Err(PatternError::BadTag(cell)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.push(row);
+        }
+
+        pattern.width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        pattern.height = rows.len();
+        pattern.cells = rows
+            .into_iter()
+            .flat_map(|mut row| {
+                row.resize(pattern.width, 0);
+                row
+            })
+            .collect();
+        Ok(pattern)
+    }
+
+    fn read_comment(&mut self, comment: &str) {
+        let mut chars = comment.chars();
+        let kind = chars.next();
+        let text = chars.as_str().trim().to_string();
+        match kind {
+            Some('N') => self.name = Some(text),
+            Some('C' | 'c') => self.comments.push(text),
+            Some('r') => self.rule = Rule::parse(&text).ok().or(self.rule),
+            _ => {}
+        }
+    }
+
+    // the smallest pattern that still holds every live or dying cell
+    pub fn trimmed(&self) -> Self {
+        let live = || {
+            (0..self.height)
+                .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+                .filter(|&(x, y)| self.state(x, y) != 0)
+        };
+        let Some(min_x) = live().map(|(x, _)| x).min() else {
+            return Pattern {
+                cells: Vec::new(),
+                width: 0,
+                height: 0,
+                ..self.clone()
+            };
+        };
+        let max_x = live().map(|(x, _)| x).max().unwrap();
+        let min_y = live().map(|(_, y)| y).min().unwrap();
+        let max_y = live().map(|(_, y)| y).max().unwrap();
+
+        let (width, height) = (max_x - min_x + 1, max_y - min_y + 1);
+        let cells = (min_y..=max_y)
+            .flat_map(|y| (min_x..=max_x).map(move |x| (x, y)))
+            .map(|(x, y)| self.state(x, y))
+            .collect();
+        Pattern {
+            width,
+            height,
+            cells,
+            ..self.clone()
+        }
+    }
+
+    // turned clockwise by quarter_turns
+    pub fn rotated(&self, quarter_turns: u32) -> Self {
+        (0..quarter_turns % 4).fold(self.clone(), |pattern, _| {
+            let (width, height) = (pattern.height, pattern.width);
+            let cells = (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| pattern.state(y, pattern.height - 1 - x))
+                .collect();
+            Pattern {
+                width,
+                height,
+                cells,
+                ..pattern
+            }
+        })
+    }
+
+    // mirrored left to right
+    pub fn flipped(&self) -> Self {
+        let cells = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .map(|(x, y)| self.state(self.width - 1 - x, y))
+            .collect();
+        Pattern {
+            cells,
+            ..self.clone()
+        }
+    }
+
+    pub fn write(&self, format: PatternFormat) -> String {
+        match format {
+            PatternFormat::Rle => self.to_rle(),
+            PatternFormat::Plaintext => self.to_plaintext(),
+        }
+    }
+
+    pub fn to_rle(&self) -> String {
+        let mut out = String::new();
+        if let Some(name) = &self.name {
+            writeln!(out, "#N {name}").unwrap();
+        }
+        for comment in &self.comments {
+            writeln!(out, "#C {comment}").unwrap();
+        }
+        write!(out, "x = {}, y = {}", self.width, self.height).unwrap();
+        if let Some(rule) = self.rule {
+            write!(out, ", rule = {rule}").unwrap();
+        }
+        out.push('\n');
+
+        // runs of (count, tag), trailing dead cells and blank rows are left
+        // for the row and pattern ends to imply. Patterns with dying cells
+        // name every state with multi-state letters, the rest stick to b and o
+        let multi_state = self.cells.iter().any(|state| *state > 1);
+        let mut runs = Vec::new();
+        for y in 0..self.height {
+            if y > 0 {
+                push_run(&mut runs, "$".to_string());
+            }
+            let row = &self.cells[y * self.width..(y + 1) * self.width];
+            let len = row
+                .iter()
+                .rposition(|state| *state != 0)
+                .map_or(0, |last| last + 1);
+            for state in &row[..len] {
+                let tag = match (*state, multi_state) {
+                    (0, false) => "b".to_string(),
+                    (_, false) => "o".to_string(),
+                    (state, true) => state_tag(state),
+                };
+                push_run(&mut runs, tag);
+            }
+        }
+        while runs.last().is_some_and(|(_, tag)| tag == "$") {
+            runs.pop();
+        }
+        runs.push((1, "!".to_string()));
+
+        let mut line = String::new();
+        for (count, tag) in runs {
+            let item = if count > 1 {
+                format!("{count}{tag}")
+            } else {
+                tag
+            };
+            if line.len() + item.len() > RLE_LINE {
+                out.push_str(&line);
+                out.push('\n');
+                line.clear();
+            }
+            line.push_str(&item);
+        }
+        out.push_str(&line);
+        out.push('\n');
+        out
+    }
+
+    // plaintext only knows alive and dead, so dying cells are written as
+    // dead and only RLE keeps them
+    pub fn to_plaintext(&self) -> String {
+        let mut out = String::new();
+        if let Some(name) = &self.name {
+            writeln!(out, "!Name: {name}").unwrap();
+        }
+        for comment in &self.comments {
+            writeln!(out, "!{comment}").unwrap();
+        }
+        for row in self.cells.chunks(self.width.max(1)) {
+            out.extend(row.iter().map(|state| if *state == 1 { 'O' } else { '.' }));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn push_run(runs: &mut Vec<(usize, String)>, tag: String) {
+    match runs.last_mut() {
+        Some((count, last)) if *last == tag => *count += 1,
+        _ => runs.push((1, tag)),
+    }
+}
+
+// the multi-state RLE letters for a state, with . for dead
+fn state_tag(state: u8) -> String {
+    if state == 0 {
+        return ".".to_string();
+    }
+    let (prefix, letter) = ((state - 1) / STATE_LETTERS, (state - 1) % STATE_LETTERS);
+    let letter = (b'A' + letter) as char;
+    match prefix {
+        0 => letter.to_string(),
+        prefix => format!("{}{letter}", (b'p' + prefix - 1) as char),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glider() -> Pattern {
+        Pattern::parse(LIBRARY[0].1).unwrap()
+    }
+
+    fn live(pattern: &Pattern) -> Vec<(usize, usize)> {
+        (0..pattern.height)
+            .flat_map(|y| (0..pattern.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| pattern.state(x, y) != 0)
+            .collect()
+    }
+
+    #[test]
+    fn library_sizes() {
+        let sizes = [
+            ("Glider", 3, 3, 5),
+            ("Lightweight spaceship", 5, 4, 9),
+            ("Pulsar", 13, 13, 48),
+            ("Gosper glider gun", 36, 9, 36),
+            ("Acorn", 7, 3, 7),
+            ("R-pentomino", 3, 3, 5),
+        ];
+        assert_eq!(LIBRARY.len(), sizes.len());
+        for ((name, rle), (expected, width, height, population)) in LIBRARY.iter().zip(sizes) {
+            assert_eq!(*name, expected);
+            let pattern = Pattern::parse(rle).unwrap();
+            assert_eq!(pattern.name.as_deref(), Some(*name));
+            assert_eq!((pattern.width, pattern.height), (width, height), "{name}");
+            assert_eq!(live(&pattern).len(), population, "{name}");
+            assert_eq!(pattern.rule, Some(Rule::parse("B3/S23").unwrap()));
+        }
+    }
+
+    #[test]
+    fn glider_cells() {
+        assert_eq!(live(&glider()), [(0, 0), (1, 0), (2, 0), (2, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn metadata() {
+        let pattern = Pattern::parse_rle(
+            "#N Thing\n#C first\n#c second\n#r B36/S23\n#O someone\nx = 2, y = 1\n2o!",
+        )
+        .unwrap();
+        assert_eq!(pattern.name.as_deref(), Some("Thing"));
+        assert_eq!(pattern.comments, ["first", "second"]);
+        assert_eq!(pattern.rule, Some(Rule::parse("B36/S23").unwrap()));
+        // a rule in the header wins over #r
+        let pattern = Pattern::parse_rle("#r B36/S23\nx = 1, y = 1, rule = B2/S\no!").unwrap();
+        assert_eq!(pattern.rule, Some(Rule::parse("B2/S").unwrap()));
+    }
+
+    #[test]
+    fn rle_round_trip() {
+        for (name, rle) in LIBRARY {
+            let pattern = Pattern::parse(rle).unwrap();
+            let written = pattern.to_rle();
+            assert_eq!(Pattern::parse(&written).unwrap(), pattern, "{name}");
+            assert!(written.lines().all(|line| line.len() <= RLE_LINE));
+        }
+    }
+
+    #[test]
+    fn plaintext_round_trip() {
+        for (name, rle) in LIBRARY {
+            let pattern = Pattern::parse(rle).unwrap();
+            let text = pattern.to_plaintext();
+            let parsed = Pattern::parse(&text).unwrap();
+            assert_eq!(parsed.name, pattern.name, "{name}");
+            assert_eq!(parsed.comments, pattern.comments, "{name}");
+            assert_eq!(
+                (parsed.width, parsed.height),
+                (pattern.width, pattern.height)
+            );
+            assert_eq!(live(&parsed), live(&pattern), "{name}");
+            // and back to RLE, the rule is all plaintext can't carry
+            let rle = Pattern::parse(&parsed.to_rle()).unwrap();
+            assert_eq!(
+                Pattern {
+                    rule: pattern.rule,
+                    ..rle
+                },
+                pattern,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn multi_state_round_trip() {
+        // a live cell trailing two dying ones, and a state past the prefixes
+        let mut pattern = Pattern::from_cells(4, 2, vec![1, 2, 3, 0, 0, 0, 30, 255]);
+        pattern.rule = Some(Rule::parse("B2/S/C255").unwrap());
+        let written = pattern.to_rle();
+        assert!(written.ends_with("ABC$2.pFyO!\n"), "{written}");
+        assert_eq!(Pattern::parse(&written).unwrap(), pattern);
+        // rotating keeps states where they land
+        let turned = pattern.rotated(1);
+        assert_eq!(Pattern::parse(&turned.to_rle()).unwrap(), turned);
+        assert_eq!(turned.state(1, 2), 3);
+    }
+
+    #[test]
+    fn multi_state_letters() {
+        let pattern = Pattern::parse_rle("x = 5, y = 1\n2A.2X!").unwrap();
+        assert_eq!(pattern.cells, [1, 1, 0, 24, 24]);
+        let pattern = Pattern::parse_rle("x = 3, y = 1\npAqAyO!").unwrap();
+        assert_eq!(pattern.cells, [25, 49, 255]);
+        // there's no state past 255, and a prefix needs a letter
+        assert_eq!(
+            Pattern::parse_rle("x = 1, y = 1\nyP!"),
+            Err(PatternError::BadTag('P'))
+        );
+        assert_eq!(
+            Pattern::parse_rle("x = 1, y = 1\npo!"),
+            Err(PatternError::BadTag('o'))
+        );
+        assert_eq!(
+            Pattern::parse_rle("x = 1, y = 1\nZ!"),
+            Err(PatternError::BadTag('Z'))
+        );
+        // trimming keeps dying cells
+        let pattern = Pattern::parse_rle("x = 4, y = 1\n.B2.!").unwrap().trimmed();
+        assert_eq!((pattern.width, pattern.cells), (1, vec![2]));
+        // and plaintext has no way to say dying
+        let pattern = Pattern::from_cells(3, 1, vec![1, 2, 0]);
+        assert_eq!(pattern.to_plaintext(), "O..\n");
+    }
+
+    #[test]
+    fn plaintext() {
+        let pattern = Pattern::parse("!Name: Blinker\n!a comment\n.O\n*\n..O\n").unwrap();
+        assert_eq!(pattern.name.as_deref(), Some("Blinker"));
+        assert_eq!(pattern.comments, ["a comment"]);
+        assert_eq!((pattern.width, pattern.height), (3, 3));
+        assert_eq!(live(&pattern), [(1, 0), (0, 1), (2, 2)]);
+        assert_eq!(Pattern::parse("..x"), Err(PatternError::BadTag('x')));
+    }
+
+    #[test]
+    fn rotated() {
+        // an L, three tall with its foot to the right
+        let l = Pattern::parse("O.\nO.\nOO\n").unwrap();
+        let quarter = l.rotated(1);
+        assert_eq!((quarter.width, quarter.height), (3, 2));
+        assert_eq!(quarter, Pattern::parse("OOO\nO..\n").unwrap());
+        assert_eq!(l.rotated(2), Pattern::parse("OO\n.O\n.O\n").unwrap());
+        assert_eq!(l.rotated(3), Pattern::parse("..O\nOOO\n").unwrap());
+        assert_eq!(l.rotated(4), l);
+        assert_eq!(l.rotated(5), quarter);
+        assert_eq!(quarter.rotated(3), l);
+    }
+
+    #[test]
+    fn flipped() {
+        let l = Pattern::parse("O.\nO.\nOO\n").unwrap();
+        assert_eq!(l.flipped(), Pattern::parse(".O\n.O\nOO\n").unwrap());
+        assert_eq!(l.flipped().flipped(), l);
+        // flipping then turning twice mirrors top to bottom
+        assert_eq!(
+            l.flipped().rotated(2),
+            Pattern::parse("OO\nO.\nO.\n").unwrap()
+        );
+    }
+
+    #[test]
+    fn turns_keep_metadata() {
+        let glider = glider();
+        let turned = glider.rotated(1).flipped();
+        assert_eq!(turned.name, glider.name);
+        assert_eq!(turned.rule, glider.rule);
+        assert_eq!(live(&turned).len(), live(&glider).len());
+    }
+
+    #[test]
+    fn trimmed() {
+        let pattern = Pattern::parse("....\n.O..\n..O.\n....\n").unwrap();
+        assert_eq!(pattern.trimmed(), Pattern::parse("O.\n.O\n").unwrap());
+        let empty = Pattern::parse("...\n...\n").unwrap().trimmed();
+        assert_eq!((empty.width, empty.height), (0, 0));
+    }
+
+    #[test]
+    fn bad_rle() {
+        assert_eq!(
+            Pattern::parse_rle("#N nothing"),
+            Err(PatternError::MissingHeader)
+        );
+        assert_eq!(
+            Pattern::parse_rle("x = two, y = 1\no!"),
+            Err(PatternError::BadHeader("x = two, y = 1".to_string()))
+        );
+        assert_eq!(
+            Pattern::parse_rle("x = 2, y = 1\n3o!"),
+            Err(PatternError::TooBig)
+        );
+        assert_eq!(
+            Pattern::parse_rle("x = 2, y = 1\n$o!"),
+            Err(PatternError::TooBig)
+        );
+        assert_eq!(
+            Pattern::parse_rle("x = 2, y = 1\noz!"),
+            Err(PatternError::BadTag('z'))
+        );
+        assert!(matches!(
+            Pattern::parse_rle("x = 1, y = 1, rule = B9/S"),
+            Err(PatternError::Rule(_))
+        ));
+    }
+
+    #[test]
+    fn huge_header() {
+        assert_eq!(
+            Pattern::parse_rle("x = 4097, y = 1\no!"),
+            Err(PatternError::TooLarge(4097, 1))
+        );
+        assert_eq!(
+            Pattern::parse_rle("x = 1, y = 99999999999\no!"),
+            Err(PatternError::TooLarge(1, 99999999999))
+        );
+        assert!(Pattern::parse_rle("x = 4096, y = 1\no!").is_ok());
+    }
+
+    #[test]
+    fn huge_runs() {
+        // too many digits to fit a usize
+        assert_eq!(
+            Pattern::parse_rle("x = 2, y = 2\n99999999999999999999999o!"),
+            Err(PatternError::TooBig)
+        );
+        // fits, but running off the end would wrap x
+        let run = usize::MAX;
+        assert_eq!(
+            Pattern::parse_rle(&format!("x = 2, y = 2\nb{run}o!")),
+            Err(PatternError::TooBig)
+        );
+        assert_eq!(
+            Pattern::parse_rle(&format!("x = 2, y = 2\n{run}b{run}bo!")),
+            Err(PatternError::TooBig)
+        );
+        assert_eq!(
+            Pattern::parse_rle(&format!("x = 2, y = 2\n{run}$o!")),
+            Err(PatternError::TooBig)
+        );
+    }
+}
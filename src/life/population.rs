@@ -0,0 +1,93 @@
+use std::convert::Infallible;
+
+use utility::prelude::*;
+
+use web_sys::{WebGl2RenderingContext, WebGlShader};
+
+type GL = WebGl2RenderingContext;
+
+// must match BLOCK in population.frag
+const BLOCK: i32 = 4;
+
+render_pipeline!(PopulationPipeline, "../shaders/population.frag");
+
+// Counts live cells by summing the board down in BLOCK by BLOCK steps until
+// a single texel is left, so only one pixel gets read back. Sums are exact
+// in a float up to 2^24, which covers the largest board
+pub struct Population {
+    pipeline: PopulationPipeline,
+    // each a BLOCK times smaller than the one before, the last one is 1x1
+    levels: Vec<BufferedTexture>,
+    board_size: (i32, i32),
+}
+
+impl Population {
+    pub fn create(context: &WebGl2RenderingContext, quad_vert_shader: &WebGlShader) -> Self {
+        let population_frag_shader = compile_shader(
+            context,
+            GL::FRAGMENT_SHADER,
+            include_str!("../shaders/population.frag"),
+        )
+        .unwrap();
+        let program = Program::create(context, quad_vert_shader, &population_frag_shader);
+
+        Population {
+            pipeline: PopulationPipeline::create(context, program),
+            levels: Vec::new(),
+            board_size: (0, 0),
+        }
+    }
+
+    pub fn count(
+        &mut self,
+        context: &WebGl2RenderingContext,
+        quad: &Quad,
+        board: &BufferedTexture,
+    ) -> u32 {
+        let board_size = (board.width(), board.height());
+        if self.board_size != board_size {
+            self.levels = make_levels(context, board_size);
+            self.board_size = board_size;
+        }
+
+        let mut source = board;
+        for level in &self.levels {
+            self.pipeline.set_arguments(
+                context,
+                source,
+                (source.width() as f32, source.height() as f32),
+            );
+            quad.blit(Some(level));
+            source = level;
+        }
+        source.read_rgba_f32()[0] as u32
+    }
+}
+
+fn make_levels(context: &WebGl2RenderingContext, board_size: (i32, i32)) -> Vec<BufferedTexture> {
+    let mut levels = Vec::new();
+    let (mut width, mut height) = board_size;
+    while width > 1 || height > 1 {
+        width = (width + BLOCK - 1) / BLOCK;
+        height = (height + BLOCK - 1) / BLOCK;
+        levels.push(BufferedTexture::create(
+            context,
+            GL::TEXTURE_2D,
+            0,
+            GL::RGBA32F,
+            width,
+            height,
+            0,
+            GL::RGBA,
+            GL::FLOAT,
+            None::<Infallible>,
+            &[
+                (GL::TEXTURE_MIN_FILTER, GL::NEAREST),
+                (GL::TEXTURE_MAG_FILTER, GL::NEAREST),
+                (GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE),
+                (GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE),
+            ],
+        ));
+    }
+    levels
+}
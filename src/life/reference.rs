@@ -0,0 +1,184 @@
+use super::rule::Rule;
+
+// neighbour lookups period gets to spend, it runs inside an animation frame
+// so this is kept to a few milliseconds of work
+const PERIOD_BUDGET: usize = 8_000_000;
+
+// Cell states out of the board's RGBA bytes, decoded the same way life.frag
+// does. 0 is dead, 1 alive and anything higher dying
+pub fn states(rgba: &[u8]) -> Vec<u8> {
+    rgba.chunks_exact(4)
+        .map(|cell| if cell[0] > 0 { 1 } else { cell[1] })
+        .collect()
+}
+
+// the other way round, the RGBA bytes a board holds for a state
+pub fn texel(state: u8) -> [u8; 4] {
+    match state {
+        1 => [255; 4],
+        state => [0, state, 0, 255],
+    }
+}
+
+// One generation on the cpu, the slow but obvious version of life.frag to
+// check it against. Rows run from the bottom and the board wraps at the edges
+pub fn step(rule: &Rule, width: usize, height: usize, cells: &[u8]) -> Vec<u8> {
+    let neighbours = rule.neighbours();
+    let alive = |x: usize, y: usize, (dx, dy): (i32, i32)| {
+        let x = (x as i32 + dx).rem_euclid(width as i32) as usize;
+        let y = (y as i32 + dy).rem_euclid(height as i32) as usize;
+        cells[y * width + x] == 1
+    };
+
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let count = neighbours
+                .iter()
+                .filter(|offset| alive(x, y, **offset))
+                .count() as u32;
+            rule.next_state(cells[y * width + x] as u32, count) as u8
+        })
+        .collect()
+}
+
+// most generations period can step through on a board this size without
+// going over PERIOD_BUDGET, zero when even one is too many
+pub fn affordable_generations(rule: &Rule, width: usize, height: usize) -> u64 {
+    let per_generation = width * height * rule.neighbours().len().max(1);
+    (PERIOD_BUDGET / per_generation) as u64
+}
+
+// generations until cells come round to the same states again, giving up
+// after most
+pub fn period(rule: &Rule, width: usize, height: usize, cells: &[u8], most: u64) -> Option<u64> {
+    let mut next = cells.to_vec();
+    for generation in 1..=most {
+        next = step(rule, width, height, &next);
+        if next == cells {
+            return Some(generation);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a board with the given cells alive, or in the given state
+    fn board(width: usize, height: usize, cells: &[(usize, usize, u8)]) -> Vec<u8> {
+        let mut board = vec![0; width * height];
+        for &(x, y, state) in cells {
+            board[y * width + x] = state;
+        }
+        board
+    }
+
+    fn run(rule: &Rule, size: usize, cells: &[u8], generations: usize) -> Vec<u8> {
+        (0..generations).fold(cells.to_vec(), |cells, _| step(rule, size, size, &cells))
+    }
+
+    #[test]
+    fn states_from_rgba() {
+        let rgba = [255, 255, 255, 0, 0, 0, 0, 255, 0, 3, 0, 255];
+        assert_eq!(states(&rgba), [1, 0, 3]);
+        let texels: Vec<u8> = [0, 1, 2, 255].into_iter().flat_map(texel).collect();
+        assert_eq!(states(&texels), [0, 1, 2, 255]);
+    }
+
+    #[test]
+    fn period_budget() {
+        let conway = Rule::parse("B3/S23").unwrap();
+        assert!(affordable_generations(&conway, 128, 128) >= 30);
+        // big boards and wide neighbourhoods never reach the cpu
+        assert_eq!(affordable_generations(&conway, 1024, 1024), 0);
+        assert_eq!(affordable_generations(&conway, 4096, 4096), 0);
+        let globe = Rule::parse("R7,C0,M1,S100..200,B75..170,NM").unwrap();
+        assert_eq!(affordable_generations(&globe, 512, 512), 0);
+    }
+
+    #[test]
+    fn blinker() {
+        let conway = Rule::parse("B3/S23").unwrap();
+        let across = board(5, 5, &[(1, 2, 1), (2, 2, 1), (3, 2, 1)]);
+        let down = board(5, 5, &[(2, 1, 1), (2, 2, 1), (2, 3, 1)]);
+        assert_eq!(run(&conway, 5, &across, 1), down);
+        assert_eq!(run(&conway, 5, &across, 2), across);
+    }
+
+    #[test]
+    fn glider() {
+        let conway = Rule::parse("B3/S23").unwrap();
+        // rows from the bottom, so this one heads right and down
+        let shape = [(1, 4), (2, 3), (0, 2), (1, 2), (2, 2)];
+        let at = |dx: usize, dy: usize| {
+            let cells: Vec<_> = shape
+                .iter()
+                .map(|&(x, y)| ((x + dx) % 8, (y + 8 - dy) % 8, 1))
+                .collect();
+            board(8, 8, &cells)
+        };
+        assert_eq!(run(&conway, 8, &at(0, 0), 4), at(1, 1));
+        // all the way round the wrapping board and back
+        assert_eq!(run(&conway, 8, &at(0, 0), 32), at(0, 0));
+    }
+
+    #[test]
+    fn brians_brain() {
+        let brain = Rule::parse("B2/S/C3").unwrap();
+        let pair = board(6, 6, &[(2, 2, 1), (3, 2, 1)]);
+        // nothing survives, the pair starts dying and cells touching both
+        // are born above and below it
+        let next = board(
+            6,
+            6,
+            &[
+                (2, 2, 2),
+                (3, 2, 2),
+                (2, 1, 1),
+                (3, 1, 1),
+                (2, 3, 1),
+                (3, 3, 1),
+            ],
+        );
+        assert_eq!(run(&brain, 6, &pair, 1), next);
+        // dying cells don't count as neighbours and are gone a step later
+        let after = run(&brain, 6, &pair, 2);
+        assert_eq!(after[2 * 6 + 2], 0);
+        assert_eq!(after[2 * 6 + 3], 0);
+        assert_eq!(after[6 + 2], 2);
+    }
+
+    #[test]
+    fn periods() {
+        let conway = Rule::parse("B3/S23").unwrap();
+        let block = board(6, 6, &[(2, 2, 1), (3, 2, 1), (2, 3, 1), (3, 3, 1)]);
+        assert_eq!(period(&conway, 6, 6, &block, 10), Some(1));
+        let blinker = board(5, 5, &[(1, 2, 1), (2, 2, 1), (3, 2, 1)]);
+        assert_eq!(period(&conway, 5, 5, &blinker, 10), Some(2));
+        assert_eq!(period(&conway, 5, 5, &blinker, 1), None);
+        // a glider on an 8x8 board is back where it started after 32
+        let glider = board(
+            8,
+            8,
+            &[(1, 4, 1), (2, 3, 1), (0, 2, 1), (1, 2, 1), (2, 2, 1)],
+        );
+        assert_eq!(period(&conway, 8, 8, &glider, 100), Some(32));
+    }
+
+    #[test]
+    fn larger_than_life() {
+        // Bugs needs 34 of the 121 cells around a cell alive, a lone 3x3
+        // block is far too sparse so it dies and nothing is born
+        let bugs = Rule::parse("R5,C0,M1,S34..58,B34..45,NM").unwrap();
+        let block = board(
+            16,
+            16,
+            &(7..10)
+                .flat_map(|y| (7..10).map(move |x| (x, y, 1)))
+                .collect::<Vec<_>>(),
+        );
+        assert!(run(&bugs, 16, &block, 1).iter().all(|state| *state == 0));
+    }
+}
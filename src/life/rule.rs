@@ -0,0 +1,519 @@
+use std::fmt;
+
+// must match MAX_RADIUS in life.frag
+pub const MAX_RADIUS: u32 = 7;
+
+// dying states are kept in a byte
+pub const MAX_STATES: u32 = 255;
+
+// neighbour counts a rule reacts to
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Counts {
+    // bit n is set for n neighbours, B/S rules only count up to 8
+    Mask(u16),
+    // inclusive, for Larger than Life rules
+    Range(u32, u32),
+}
+
+impl Counts {
+    pub fn contains(&self, neighbours: u32) -> bool {
+        match *self {
+            Self::Mask(mask) => neighbours < 16 && mask & 1 << neighbours != 0,
+            Self::Range(min, max) => (min..=max).contains(&neighbours),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Neighbourhood {
+    // every cell in the square out to the radius
+    Moore,
+    // cells within the radius in taxicab distance
+    VonNeumann,
+    // cells within the radius as the crow flies
+    Circular,
+}
+
+impl Neighbourhood {
+    fn letter(&self) -> char {
+        match self {
+            Self::Moore => 'M',
+            Self::VonNeumann => 'N',
+            Self::Circular => 'C',
+        }
+    }
+}
+
+// An outer totalistic rule. B/S rules like B3/S23 look at the eight cells
+// around each one, Larger than Life rules like R5,C0,M1,S34..58,B34..45,NM
+// reach further. Either can have more than two states (Generations), live
+// cells that stop surviving then spend a generation in each dying state
+// before they're dead, and only live cells count as neighbours
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rule {
+    pub birth: Counts,
+    pub survival: Counts,
+    // dead, alive, then states - 2 dying states
+    pub states: u32,
+    pub radius: u32,
+    pub neighbourhood: Neighbourhood,
+    // whether a live cell counts itself
+    pub middle: bool,
+}
+
+pub const CONWAY: Rule = Rule {
+    birth: Counts::Mask(1 << 3),
+    survival: Counts::Mask(1 << 2 | 1 << 3),
+    states: 2,
+    radius: 1,
+    neighbourhood: Neighbourhood::Moore,
+    middle: false,
+};
+
+// rule strings suggested next to the rule input
+pub const PRESETS: &[(&str, &str)] = &[
+    ("B3/S23", "Conway's Life"),
+    ("B36/S23", "HighLife"),
+    ("B2/S", "Seeds"),
+    ("B3678/S34678", "Day & Night"),
+    ("B1357/S1357", "Replicator"),
+    ("B368/S245", "Morley"),
+    ("B3/S012345678", "Life without Death"),
+    ("B2/S/C3", "Brian's Brain"),
+    ("B2/S345/C4", "Star Wars"),
+    ("B34/S345/C5", "Swirl"),
+    ("R5,C0,M1,S34..58,B34..45,NM", "Bugs"),
+    ("R4,C0,M1,S41..81,B41..81,NM", "Majority"),
+    ("R7,C0,M1,S100..200,B75..170,NM", "Globe"),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum RuleError {
+    // not laid out like any rule notation
+    Shape,
+    MissingPart(char),
+    BadCount(String),
+    BadRadius(u32),
+    BadStates(u32),
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shape => write!(
+                f,
+                "rules look like B3/S23, B2/S/C3 or R5,C0,M1,S34..58,B34..45,NM"
+            ),
+            Self::MissingPart(part) => write!(f, "no {part} part"),
+            Self::BadCount(count) => write!(f, "{count} isn't a neighbour count this rule can see"),
+            Self::BadRadius(radius) => write!(f, "radius {radius} isn't from 1 to {MAX_RADIUS}"),
+            Self::BadStates(states) => write!(f, "{states} states isn't from 2 to {MAX_STATES}"),
+        }
+    }
+}
+
+impl Rule {
+    // takes B3/S23 style strings with the parts in either order and an
+    // optional C part for the state count, the older S/B and S/B/C forms like
+    // 23/3 that show up in pattern files, or Larger than Life's comma
+    // separated form. Case doesn't matter and whitespace is ignored
+    pub fn parse(text: &str) -> Result<Self, RuleError> {
+        let text: String = text
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        if text.contains(',') {
+            Self::parse_larger(&text)
+        } else {
+            Self::parse_slashed(&text)
+        }
+    }
+
+    fn parse_slashed(text: &str) -> Result<Self, RuleError> {
+        let parts: Vec<&str> = text.split('/').collect();
+        let (birth, survival, states) = if parts.iter().all(|part| is_digits(part)) {
+            match parts[..] {
+                [survival, birth] => (birth, survival, None),
+                [survival, birth, states] => (birth, survival, Some(states)),
+                _ => return Err(RuleError::Shape),
+            }
+        } else {
+            let (mut birth, mut survival, mut states) = (None, None, None);
+            for part in &parts {
+                let (slot, digits) = match split_key(part) {
+                    (Some('B'), digits) => (&mut birth, digits),
+                    (Some('S'), digits) => (&mut survival, digits),
+                    (Some('C' | 'G'), digits) => (&mut states, digits),
+                    _ => return Err(RuleError::Shape),
+                };
+                if slot.replace(digits).is_some() {
+                    return Err(RuleError::Shape);
+                }
+            }
+            (
+                birth.ok_or(RuleError::MissingPart('B'))?,
+                survival.ok_or(RuleError::MissingPart('S'))?,
+                states,
+            )
+        };
+        let states = match states {
+            Some(states) => parse_states(states)?,
+            None => 2,
+        };
+        Ok(Rule {
+            birth: Counts::Mask(mask(birth)?),
+            survival: Counts::Mask(mask(survival)?),
+            states,
+            ..CONWAY
+        })
+    }
+
+    fn parse_larger(text: &str) -> Result<Self, RuleError> {
+        let mut rule = Rule {
+            radius: 0,
+            ..CONWAY
+        };
+        let (mut birth, mut survival) = (None, None);
+        for part in text.split(',') {
+            let (key, value) = split_key(part);
+            match key {
+                Some('R') => {
+                    let radius = parse_number(value)?;
+                    if !(1..=MAX_RADIUS).contains(&radius) {
+                        return Err(RuleError::BadRadius(radius));
+                    }
+                    rule.radius = radius;
+                }
+                // C0 and C1 both mean plain two state life
+                Some('C') => rule.states = parse_number(value)?.max(2),
+                Some('M') => rule.middle = value == "1",
+                Some('S') => survival = Some(value),
+                Some('B') => birth = Some(value),
+                Some('N') => {
+                    rule.neighbourhood = match value {
+                        "M" => Neighbourhood::Moore,
+                        "N" => Neighbourhood::VonNeumann,
+                        "C" => Neighbourhood::Circular,
+                        _ => return Err(RuleError::Shape),
+                    }
+                }
+                _ => return Err(RuleError::Shape),
+            }
+        }
+        if rule.radius == 0 {
+            return Err(RuleError::MissingPart('R'));
+        }
+        if rule.states > MAX_STATES {
+            return Err(RuleError::BadStates(rule.states));
+        }
+        let most = rule.neighbours().len() as u32;
+        rule.birth = range(birth.ok_or(RuleError::MissingPart('B'))?, most)?;
+        rule.survival = range(survival.ok_or(RuleError::MissingPart('S'))?, most)?;
+        Ok(rule)
+    }
+
+    pub fn births(&self, neighbours: u32) -> bool {
+        self.birth.contains(neighbours)
+    }
+
+    pub fn survives(&self, neighbours: u32) -> bool {
+        self.survival.contains(neighbours)
+    }
+
+    // offsets of the cells counted as neighbours, the middle one included
+    // when the rule counts it
+    pub fn neighbours(&self) -> Vec<(i32, i32)> {
+        let radius = self.radius as i32;
+        (-radius..=radius)
+            .flat_map(|dy| (-radius..=radius).map(move |dx| (dx, dy)))
+            .filter(|&(dx, dy)| match self.neighbourhood {
+                Neighbourhood::Moore => true,
+                Neighbourhood::VonNeumann => dx.abs() + dy.abs() <= radius,
+                Neighbourhood::Circular => dx * dx + dy * dy <= radius * radius,
+            })
+            .filter(|&offset| self.middle || offset != (0, 0))
+            .collect()
+    }
+
+    // the state a cell with the given state and live neighbours moves to,
+    // 0 is dead, 1 alive and anything higher dying
+    pub fn next_state(&self, state: u32, neighbours: u32) -> u32 {
+        match state {
+            0 => self.births(neighbours) as u32,
+            1 if self.survives(neighbours) => 1,
+            _ if state + 1 < self.states => state + 1,
+            _ => 0,
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.birth, self.survival) {
+            (Counts::Mask(_), Counts::Mask(_)) => {
+                write!(f, "B")?;
+                for count in (0..=8).filter(|count| self.births(*count)) {
+                    write!(f, "{count}")?;
+                }
+                write!(f, "/S")?;
+                for count in (0..=8).filter(|count| self.survives(*count)) {
+                    write!(f, "{count}")?;
+                }
+                if self.states > 2 {
+                    write!(f, "/C{}", self.states)?;
+                }
+                Ok(())
+            }
+            _ => {
+                let states = if self.states > 2 { self.states } else { 0 };
+                write!(
+                    f,
+                    "R{},C{states},M{},S{},B{},N{}",
+                    self.radius,
+                    self.middle as u32,
+                    RangeText(self.survival),
+                    RangeText(self.birth),
+                    self.neighbourhood.letter(),
+                )
+            }
+        }
+    }
+}
+
+// writes Counts in Larger than Life's min..max form
+struct RangeText(Counts);
+
+impl fmt::Display for RangeText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Counts::Range(min, max) => write!(f, "{min}..{max}"),
+            Counts::Mask(_) => Ok(()),
+        }
+    }
+}
+
+// the leading letter of a rule part and what follows it
+fn split_key(part: &str) -> (Option<char>, &str) {
+    let mut chars = part.chars();
+    (chars.next(), chars.as_str())
+}
+
+fn is_digits(text: &str) -> bool {
+    text.chars().all(|c| c.is_ascii_digit())
+}
+
+fn parse_number(text: &str) -> Result<u32, RuleError> {
+    text.parse()
+        .map_err(|_| RuleError::BadCount(text.to_string()))
+}
+
+fn parse_states(text: &str) -> Result<u32, RuleError> {
+    let states = parse_number(text)?;
+    if (2..=MAX_STATES).contains(&states) {
+        Ok(states)
+    } else {
+        Err(RuleError::BadStates(states))
+    }
+}
+
+fn mask(digits: &str) -> Result<u16, RuleError> {
+    digits
+        .chars()
+        .try_fold(0, |mask, digit| match digit.to_digit(10) {
+            Some(count) if count <= 8 => Ok(mask | 1 << count),
+            _ => Err(RuleError::BadCount(digit.to_string())),
+        })
+}
+
+// min..max, or a single count
+fn range(text: &str, most: u32) -> Result<Counts, RuleError> {
+    let (min, max) = text.split_once("..").unwrap_or((text, text));
+    let (min, max) = (parse_number(min)?, parse_number(max)?);
+    if max > most {
+        return Err(RuleError::BadCount(max.to_string()));
+    }
+    Ok(Counts::Range(min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(mask: Counts) -> Vec<u32> {
+        (0..=8).filter(|count| mask.contains(*count)).collect()
+    }
+
+    #[test]
+    fn conway() {
+        assert_eq!(Rule::parse("B3/S23"), Ok(CONWAY));
+    }
+
+    #[test]
+    fn highlife() {
+        let rule = Rule::parse("B36/S23").unwrap();
+        assert_eq!(counts(rule.birth), [3, 6]);
+        assert_eq!(counts(rule.survival), [2, 3]);
+        assert_eq!(rule.states, 2);
+    }
+
+    #[test]
+    fn empty_survival() {
+        let rule = Rule::parse("B2/S").unwrap();
+        assert_eq!(counts(rule.birth), [2]);
+        assert!(counts(rule.survival).is_empty());
+    }
+
+    #[test]
+    fn either_order() {
+        assert_eq!(Rule::parse("S23/B3"), Ok(CONWAY));
+        // the old S/B form has survival first
+        assert_eq!(Rule::parse("23/3"), Ok(CONWAY));
+        assert_eq!(Rule::parse("23/36"), Rule::parse("B36/S23"));
+    }
+
+    #[test]
+    fn case_and_whitespace() {
+        assert_eq!(Rule::parse("b3/s23"), Ok(CONWAY));
+        assert_eq!(Rule::parse(" B3 / S 23\n"), Ok(CONWAY));
+    }
+
+    #[test]
+    fn bad_digit() {
+        assert_eq!(
+            Rule::parse("B39/S23"),
+            Err(RuleError::BadCount("9".to_string()))
+        );
+        assert_eq!(
+            Rule::parse("B3/S2x"),
+            Err(RuleError::BadCount("X".to_string()))
+        );
+    }
+
+    #[test]
+    fn missing_part() {
+        assert_eq!(Rule::parse("B3"), Err(RuleError::MissingPart('S')));
+        assert_eq!(Rule::parse("S23"), Err(RuleError::MissingPart('B')));
+        assert_eq!(Rule::parse("B3/B3"), Err(RuleError::Shape));
+        assert_eq!(Rule::parse("3"), Err(RuleError::Shape));
+        assert_eq!(Rule::parse("X3/S23"), Err(RuleError::Shape));
+    }
+
+    #[test]
+    fn generations() {
+        let rule = Rule::parse("B2/S/C3").unwrap();
+        assert_eq!(rule.states, 3);
+        assert_eq!(Rule::parse("B2/S/G3"), Ok(rule));
+        // the old S/B/C form
+        assert_eq!(Rule::parse("/2/3"), Ok(rule));
+        assert_eq!(Rule::parse("345/2/4"), Rule::parse("B2/S345/C4"));
+        assert_eq!(Rule::parse("B2/S/C1"), Err(RuleError::BadStates(1)));
+        assert_eq!(Rule::parse("B2/S/C256"), Err(RuleError::BadStates(256)));
+    }
+
+    #[test]
+    fn larger_than_life() {
+        let rule = Rule::parse("R5,C0,M1,S34..58,B34..45,NM").unwrap();
+        assert_eq!(rule.radius, 5);
+        assert_eq!(rule.states, 2);
+        assert!(rule.middle);
+        assert_eq!(rule.neighbourhood, Neighbourhood::Moore);
+        assert_eq!(rule.birth, Counts::Range(34, 45));
+        assert_eq!(rule.survival, Counts::Range(34, 58));
+        // a single count is a range of one
+        let rule = Rule::parse("R2,C0,M0,S5,B3..4,NN").unwrap();
+        assert_eq!(rule.survival, Counts::Range(5, 5));
+        assert_eq!(rule.neighbourhood, Neighbourhood::VonNeumann);
+        assert_eq!(
+            Rule::parse("r2,c0,m0,s5,b3..4,nc").unwrap().neighbourhood,
+            Neighbourhood::Circular
+        );
+    }
+
+    #[test]
+    fn larger_than_life_radius() {
+        for radius in 1..=MAX_RADIUS {
+            let rule = Rule::parse(&format!("R{radius},C0,M0,S1,B1,NM")).unwrap();
+            assert_eq!(rule.radius, radius);
+        }
+        assert_eq!(
+            Rule::parse("R0,C0,M0,S1,B1,NM"),
+            Err(RuleError::BadRadius(0))
+        );
+        assert_eq!(
+            Rule::parse("R8,C0,M0,S1,B1,NM"),
+            Err(RuleError::BadRadius(8))
+        );
+        assert_eq!(
+            Rule::parse("C0,M0,S1,B1,NM"),
+            Err(RuleError::MissingPart('R'))
+        );
+    }
+
+    #[test]
+    fn larger_than_life_states() {
+        // C0 and C1 are both plain life
+        for states in [0, 1] {
+            let rule = Rule::parse(&format!("R1,C{states},M0,S2..3,B3,NM")).unwrap();
+            assert_eq!(rule.states, 2);
+        }
+        assert_eq!(Rule::parse("R1,C5,M0,S2..3,B3,NM").unwrap().states, 5);
+        assert_eq!(
+            Rule::parse("R1,C256,M0,S2..3,B3,NM"),
+            Err(RuleError::BadStates(256))
+        );
+    }
+
+    #[test]
+    fn larger_than_life_counts() {
+        // radius 1 Moore without the middle has 8 neighbours to count
+        assert!(Rule::parse("R1,C0,M0,S2..8,B3,NM").is_ok());
+        assert_eq!(
+            Rule::parse("R1,C0,M0,S2..9,B3,NM"),
+            Err(RuleError::BadCount("9".to_string()))
+        );
+        assert_eq!(
+            Rule::parse("R1,C0,M0,B3,NM"),
+            Err(RuleError::MissingPart('S'))
+        );
+        assert_eq!(Rule::parse("R1,C0,M0,S2,B3,NX"), Err(RuleError::Shape));
+    }
+
+    #[test]
+    fn neighbours() {
+        let count = |text: &str| Rule::parse(text).unwrap().neighbours().len();
+        assert_eq!(count("B3/S23"), 8);
+        assert_eq!(count("R1,C0,M1,S1,B1,NM"), 9);
+        assert_eq!(count("R2,C0,M0,S1,B1,NM"), 24);
+        assert_eq!(count("R2,C0,M0,S1,B1,NN"), 12);
+        assert_eq!(count("R2,C0,M0,S1,B1,NC"), 12);
+        assert_eq!(count("R3,C0,M0,S1,B1,NN"), 24);
+        assert_eq!(count("R3,C0,M0,S1,B1,NC"), 28);
+        assert_eq!(count("R7,C0,M1,S1,B1,NM"), 225);
+        let moore = Rule::parse("B3/S23").unwrap().neighbours();
+        assert!(!moore.contains(&(0, 0)));
+        assert!(moore.contains(&(-1, 1)));
+    }
+
+    #[test]
+    fn next_state() {
+        let brain = Rule::parse("B2/S/C3").unwrap();
+        assert_eq!(brain.next_state(0, 2), 1);
+        assert_eq!(brain.next_state(0, 3), 0);
+        assert_eq!(brain.next_state(1, 2), 2);
+        assert_eq!(brain.next_state(2, 2), 0);
+        assert_eq!(CONWAY.next_state(1, 1), 0);
+        assert_eq!(CONWAY.next_state(1, 2), 1);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for (text, _) in PRESETS {
+            let rule = Rule::parse(text).unwrap();
+            assert_eq!(rule.to_string(), *text);
+            assert_eq!(Rule::parse(&rule.to_string()), Ok(rule));
+        }
+        // written out in the usual order whatever order it came in
+        assert_eq!(Rule::parse("s32/b63").unwrap().to_string(), "B36/S23");
+    }
+}
@@ -0,0 +1,284 @@
+use utility::prelude::*;
+
+use crate::controls::SelectOption;
+
+// most generations a fixed rate catches up on in one frame
+const MAX_CATCH_UP: u32 = 16;
+
+// Fastest keeps adding generations per frame while frames take less than
+// this, and backs off when they take longer
+const FASTEST_FRAME_MS: f64 = 1000.0 / 50.0;
+
+const MAX_FASTEST: u32 = 1024;
+
+// after going over budget Fastest settles this fraction below the count
+// that did, rather than climbing straight back into it
+const FASTEST_MARGIN: u32 = 8;
+
+// frames Fastest holds steady under its ceiling before trying a little
+// higher, so a one off slow frame doesn't cap it for good
+const FASTEST_PROBE_FRAMES: u32 = 300;
+
+// generations per second
+#[derive(Clone, Copy, PartialEq)]
+pub enum Rate {
+    One = 1,
+    Two = 2,
+    Five = 5,
+    Ten = 10,
+    Twenty = 20,
+    Thirty = 30,
+    Sixty = 60,
+    OneTwenty = 120,
+    TwoForty = 240,
+    // as many as the gpu gets through while keeping the frame rate up
+    Fastest = 0,
+}
+
+impl SelectOption for Rate {
+    const ALL: &'static [Self] = &[
+        Self::One,
+        Self::Two,
+        Self::Five,
+        Self::Ten,
+        Self::Twenty,
+        Self::Thirty,
+        Self::Sixty,
+        Self::OneTwenty,
+        Self::TwoForty,
+        Self::Fastest,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::One => "1",
+            Self::Two => "2",
+            Self::Five => "5",
+            Self::Ten => "10",
+            Self::Twenty => "20",
+            Self::Thirty => "30",
+            Self::Sixty => "60",
+            Self::OneTwenty => "120",
+            Self::TwoForty => "240",
+            Self::Fastest => "Fastest",
+        }
+    }
+}
+
+impl Rate {
+    // simulated time a generation stands for, Fastest counts one per
+    // frame at 60fps so recordings of it still play back sensibly
+    pub fn step_ms(&self) -> f64 {
+        match self {
+            Self::Fastest => 1000.0 / 60.0,
+            rate => 1000.0 / *rate as u32 as f64,
+        }
+    }
+}
+
+// Decides how many generations each frame runs, separately from drawing
+// which happens every frame whatever the rate
+pub struct Pacer {
+    rate: Rate,
+    ticks: FixedTimestep,
+    // generations per frame for Fastest
+    per_frame: u32,
+    // the per_frame that last went over budget, if any has
+    ceiling: Option<u32>,
+    // frames spent at the cap since the last change to the ceiling
+    steady: u32,
+}
+
+impl Pacer {
+    pub fn new(rate: Rate) -> Self {
+        Pacer {
+            rate,
+            ticks: FixedTimestep::new(rate.step_ms(), MAX_CATCH_UP),
+            per_frame: 1,
+            ceiling: None,
+            steady: 0,
+        }
+    }
+
+    // most generations per frame Fastest grows to, a margin below the
+    // ceiling once there is one
+    fn cap(&self) -> u32 {
+        self.ceiling.map_or(MAX_FASTEST, |ceiling| {
+            (ceiling - ceiling / FASTEST_MARGIN - 1).max(1)
+        })
+    }
+
+    // elapsed is simulated time, zero while paused, and frame_ms is the
+    // real time the last frame took
+    pub fn generations(&mut self, rate: Rate, elapsed: f64, frame_ms: f64) -> u32 {
+        if rate != self.rate {
+            *self = Pacer::new(rate);
+        }
+        if rate != Rate::Fastest {
+            return self.ticks.advance(elapsed);
+        }
+        if elapsed <= 0.0 {
+            return 0;
+        }
+        if frame_ms > FASTEST_FRAME_MS {
+            // drop just below the new ceiling, frames that stay slow keep
+            // lowering it
+            self.ceiling = Some(self.per_frame);
+            self.steady = 0;
+            self.per_frame = self.cap();
+            return self.per_frame;
+        }
+        if self.per_frame >= self.cap() {
+            self.steady += 1;
+            if self.steady >= FASTEST_PROBE_FRAMES
+                && let Some(ceiling) = self.ceiling
+            {
+                let raised = ceiling + ceiling / FASTEST_MARGIN + 1;
+                self.ceiling = (raised <= MAX_FASTEST).then_some(raised);
+                self.steady = 0;
+            }
+        }
+        self.per_frame = (self.per_frame + self.per_frame / 4 + 1).min(self.cap());
+        self.per_frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAST_MS: f64 = 1000.0 / 60.0;
+    const SLOW_MS: f64 = 1000.0 / 30.0;
+
+    #[test]
+    fn fixed_rate_steps_by_elapsed_time() {
+        let mut pacer = Pacer::new(Rate::Ten);
+        assert_eq!(pacer.generations(Rate::Ten, 50.0, FAST_MS), 0);
+        assert_eq!(pacer.generations(Rate::Ten, 50.0, FAST_MS), 1);
+        assert_eq!(pacer.generations(Rate::Ten, 0.0, FAST_MS), 0);
+        assert_eq!(pacer.generations(Rate::Ten, 300.0, FAST_MS), 3);
+    }
+
+    #[test]
+    fn fixed_rate_catch_up_is_capped() {
+        let mut pacer = Pacer::new(Rate::Sixty);
+        assert_eq!(
+            pacer.generations(Rate::Sixty, 60_000.0, FAST_MS),
+            MAX_CATCH_UP
+        );
+        // the backlog is dropped rather than carried into later frames
+        assert_eq!(pacer.generations(Rate::Sixty, 0.0, FAST_MS), 0);
+    }
+
+    #[test]
+    fn rate_change_resets() {
+        let mut pacer = Pacer::new(Rate::One);
+        assert_eq!(pacer.generations(Rate::One, 900.0, FAST_MS), 0);
+        // time banked at the old rate doesn't carry over
+        assert_eq!(pacer.generations(Rate::Ten, 50.0, FAST_MS), 0);
+        assert_eq!(pacer.generations(Rate::Ten, 50.0, FAST_MS), 1);
+
+        for _ in 0..20 {
+            pacer.generations(Rate::Fastest, FAST_MS, FAST_MS);
+        }
+        pacer.generations(Rate::Fastest, FAST_MS, SLOW_MS);
+        assert_eq!(pacer.generations(Rate::Two, 100.0, FAST_MS), 0);
+        // and Fastest starts over from one with no ceiling
+        assert_eq!(pacer.generations(Rate::Fastest, FAST_MS, FAST_MS), 2);
+        for _ in 0..100 {
+            pacer.generations(Rate::Fastest, FAST_MS, FAST_MS);
+        }
+        assert_eq!(
+            pacer.generations(Rate::Fastest, FAST_MS, FAST_MS),
+            MAX_FASTEST
+        );
+    }
+
+    #[test]
+    fn fastest_grows_while_frames_are_fast() {
+        let mut pacer = Pacer::new(Rate::Fastest);
+        let mut last = 1;
+        for _ in 0..10 {
+            let per_frame = pacer.generations(Rate::Fastest, FAST_MS, FAST_MS);
+            assert!(per_frame > last);
+            last = per_frame;
+        }
+        for _ in 0..100 {
+            pacer.generations(Rate::Fastest, FAST_MS, FAST_MS);
+        }
+        assert_eq!(
+            pacer.generations(Rate::Fastest, FAST_MS, FAST_MS),
+            MAX_FASTEST
+        );
+    }
+
+    #[test]
+    fn fastest_backs_off_when_frames_are_slow() {
+        let mut pacer = Pacer::new(Rate::Fastest);
+        for _ in 0..100 {
+            pacer.generations(Rate::Fastest, FAST_MS, FAST_MS);
+        }
+        let per_frame = pacer.generations(Rate::Fastest, FAST_MS, SLOW_MS);
+        assert_eq!(per_frame, MAX_FASTEST - MAX_FASTEST / FASTEST_MARGIN - 1);
+        // never below one however slow
+        for _ in 0..100 {
+            pacer.generations(Rate::Fastest, FAST_MS, 10_000.0);
+        }
+        assert_eq!(pacer.generations(Rate::Fastest, FAST_MS, 10_000.0), 1);
+    }
+
+    #[test]
+    fn fastest_holds_below_the_budget() {
+        // frames go over budget once per_frame reaches 100
+        let frame_ms = |per_frame: u32| if per_frame >= 100 { SLOW_MS } else { FAST_MS };
+        let mut pacer = Pacer::new(Rate::Fastest);
+        let mut per_frame = 1;
+        for _ in 0..50 {
+            per_frame = pacer.generations(Rate::Fastest, FAST_MS, frame_ms(per_frame));
+        }
+        let settled = per_frame;
+        assert!((80..100).contains(&settled));
+        // and stays there, no saw-tooth, until it's time to probe again
+        for _ in 0..FASTEST_PROBE_FRAMES - 50 {
+            per_frame = pacer.generations(Rate::Fastest, FAST_MS, frame_ms(per_frame));
+            assert_eq!(per_frame, settled);
+        }
+        // a probe that goes over comes straight back to the same level
+        let mut over = 0;
+        for _ in 0..FASTEST_PROBE_FRAMES * 4 {
+            per_frame = pacer.generations(Rate::Fastest, FAST_MS, frame_ms(per_frame));
+            if per_frame >= 100 {
+                over += 1;
+            }
+            assert!(per_frame >= settled * 7 / 8);
+        }
+        assert!(over <= 4);
+    }
+
+    #[test]
+    fn fastest_recovers_from_a_slow_frame() {
+        let mut pacer = Pacer::new(Rate::Fastest);
+        for _ in 0..100 {
+            pacer.generations(Rate::Fastest, FAST_MS, FAST_MS);
+        }
+        pacer.generations(Rate::Fastest, FAST_MS, 10_000.0);
+        for _ in 0..FASTEST_PROBE_FRAMES * 40 {
+            pacer.generations(Rate::Fastest, FAST_MS, FAST_MS);
+        }
+        assert_eq!(
+            pacer.generations(Rate::Fastest, FAST_MS, FAST_MS),
+            MAX_FASTEST
+        );
+    }
+
+    #[test]
+    fn fastest_pauses() {
+        let mut pacer = Pacer::new(Rate::Fastest);
+        for _ in 0..10 {
+            pacer.generations(Rate::Fastest, FAST_MS, FAST_MS);
+        }
+        let running = pacer.generations(Rate::Fastest, FAST_MS, FAST_MS);
+        assert_eq!(pacer.generations(Rate::Fastest, 0.0, FAST_MS), 0);
+        assert!(pacer.generations(Rate::Fastest, FAST_MS, FAST_MS) > running);
+    }
+}
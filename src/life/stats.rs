@@ -0,0 +1,232 @@
+use std::collections::VecDeque;
+
+// generations of population the sparkline shows
+pub const SPARKLINE_LEN: usize = 200;
+
+// the hashes of this many records are kept, so it's the longest oscillation
+// found when every generation is recorded
+pub const MAX_PERIOD: usize = 1000;
+
+// 64 bit FNV-1a over the cell states from reference::states, so boards that
+// only differ in how a live cell's bytes were written hash the same
+pub fn hash_states(states: &[u8]) -> u64 {
+    states.iter().fold(0xcbf2_9ce4_8422_2325, |hash, state| {
+        (hash ^ *state as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+// the board came back to an earlier state, so it'll repeat forever
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Settled {
+    // the first generation of the cycle, or the first recorded one inside
+    // it when generations went by between records
+    pub generation: u64,
+    // 1 for a still life, a multiple of the real period until it's pinned
+    // down with set_period if generations went by between records
+    pub period: u64,
+}
+
+// What the board has been doing since it was last set up or edited. A
+// matching hash is taken as the same board, a collision would report a
+// cycle that isn't there but that's a 1 in 2^64 chance per pair
+#[derive(Default)]
+pub struct Stats {
+    // of the latest record, the first is generation 0
+    generation: u64,
+    populations: VecDeque<u32>,
+    // (generation, hash) of the last MAX_PERIOD records, newest last
+    hashes: VecDeque<(u64, u64)>,
+    settled: Option<Settled>,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Stats::default()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    // one per record, newest last
+    pub fn populations(&self) -> &VecDeque<u32> {
+        &self.populations
+    }
+
+    pub fn settled(&self) -> Option<Settled> {
+        self.settled
+    }
+
+    // the board as it is at generation, call once for the starting board
+    // and then as often as it's worth reading the board back. Without a hash
+    // cycles aren't looked for and the ones kept so far are dropped, since
+    // they'd have a gap
+    pub fn record(&mut self, generation: u64, population: u32, hash: Option<u64>) {
+        self.generation = generation;
+        if self.populations.len() == SPARKLINE_LEN {
+            self.populations.pop_front();
+        }
+        self.populations.push_back(population);
+
+        let Some(hash) = hash else {
+            self.hashes.clear();
+            return;
+        };
+        if self.settled.is_none()
+            && let Some(&(seen, _)) = self.hashes.iter().rev().find(|(_, seen)| *seen == hash)
+        {
+            self.settled = Some(Settled {
+                generation: seen,
+                period: generation - seen,
+            });
+        }
+        if self.hashes.len() == MAX_PERIOD {
+            self.hashes.pop_front();
+        }
+        self.hashes.push_back((generation, hash));
+    }
+
+    // the exact period, once the caller has found it
+    pub fn set_period(&mut self, period: u64) {
+        if let Some(settled) = self.settled.as_mut() {
+            settled.period = period;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // records hashes one generation apart, starting at generation 0
+    fn record_all(stats: &mut Stats, hashes: impl IntoIterator<Item = u64>) {
+        for (generation, hash) in hashes.into_iter().enumerate() {
+            stats.record(generation as u64, 1, Some(hash));
+        }
+    }
+
+    #[test]
+    fn hashes_states() {
+        assert_eq!(hash_states(&[]), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash_states(&[1, 0, 2]), hash_states(&[1, 0, 2]));
+        assert_ne!(hash_states(&[1, 0]), hash_states(&[0, 1]));
+    }
+
+    #[test]
+    fn still_life() {
+        let mut stats = Stats::new();
+        record_all(&mut stats, [7, 8, 9, 9]);
+        assert_eq!(
+            stats.settled(),
+            Some(Settled {
+                generation: 2,
+                period: 1
+            })
+        );
+        assert_eq!(stats.generation(), 3);
+    }
+
+    #[test]
+    fn blinker() {
+        let mut stats = Stats::new();
+        record_all(&mut stats, [5, 1, 2, 1]);
+        assert_eq!(
+            stats.settled(),
+            Some(Settled {
+                generation: 1,
+                period: 2
+            })
+        );
+    }
+
+    #[test]
+    fn settles_once() {
+        let mut stats = Stats::new();
+        record_all(&mut stats, [1, 2, 1, 1, 1]);
+        assert_eq!(
+            stats.settled(),
+            Some(Settled {
+                generation: 0,
+                period: 2
+            })
+        );
+    }
+
+    #[test]
+    fn nothing_repeats() {
+        let mut stats = Stats::new();
+        record_all(&mut stats, 0..50);
+        assert_eq!(stats.settled(), None);
+        assert_eq!(stats.generation(), 49);
+    }
+
+    #[test]
+    fn evicted_past_max_period() {
+        let cycle = |period: u64| (0..=period).map(move |hash| hash % period + 1000);
+        // the first hash comes back just after falling out of the window
+        let mut stats = Stats::new();
+        record_all(&mut stats, cycle(MAX_PERIOD as u64 + 1));
+        assert_eq!(stats.settled(), None);
+        // the longest period that's still found
+        let mut stats = Stats::new();
+        record_all(&mut stats, cycle(MAX_PERIOD as u64));
+        assert_eq!(
+            stats.settled(),
+            Some(Settled {
+                generation: 0,
+                period: MAX_PERIOD as u64
+            })
+        );
+    }
+
+    #[test]
+    fn no_hash_clears_history() {
+        let mut stats = Stats::new();
+        stats.record(0, 3, Some(1));
+        stats.record(1, 3, Some(2));
+        stats.record(2, 3, None);
+        stats.record(3, 3, Some(1));
+        assert_eq!(stats.settled(), None);
+        stats.record(4, 3, Some(1));
+        assert_eq!(
+            stats.settled(),
+            Some(Settled {
+                generation: 3,
+                period: 1
+            })
+        );
+    }
+
+    #[test]
+    fn skipped_generations() {
+        // a still life recorded three generations at a time looks like
+        // period 3 until the caller says otherwise
+        let mut stats = Stats::new();
+        stats.record(0, 4, Some(1));
+        stats.record(3, 4, Some(2));
+        stats.record(6, 4, Some(2));
+        assert_eq!(
+            stats.settled(),
+            Some(Settled {
+                generation: 3,
+                period: 3
+            })
+        );
+        stats.set_period(1);
+        assert_eq!(stats.settled().unwrap().period, 1);
+    }
+
+    #[test]
+    fn populations() {
+        let mut stats = Stats::new();
+        for generation in 0..SPARKLINE_LEN as u64 + 10 {
+            stats.record(generation, generation as u32, None);
+        }
+        assert_eq!(stats.populations().len(), SPARKLINE_LEN);
+        assert_eq!(stats.populations().front(), Some(&10));
+        assert_eq!(
+            stats.populations().back(),
+            Some(&(SPARKLINE_LEN as u32 + 9))
+        );
+    }
+}
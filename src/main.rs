@@ -1,6 +1,113 @@
+mod capture;
+mod controls;
 mod fluids;
+mod life;
+mod pointers;
+mod replay;
+mod sand;
+mod script;
+mod session;
+mod snapshot;
+mod transport;
+
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use web_sys::console;
+
+use controls::SelectOption;
+use snapshot::{DemoKind, Snapshot, Snapshots};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Demo {
+    Fluids,
+    Life,
+    Sand,
+}
+
+impl SelectOption for Demo {
+    const ALL: &'static [Self] = &[Self::Fluids, Self::Life, Self::Sand];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Fluids => "Fluids",
+            Self::Life => "Life",
+            Self::Sand => "Sand",
+        }
+    }
+}
+
+impl Demo {
+    fn from_kind(kind: DemoKind) -> Self {
+        match kind {
+            DemoKind::Fluids => Self::Fluids,
+            DemoKind::Life => Self::Life,
+            DemoKind::Sand => Self::Sand,
+        }
+    }
+}
+
+#[component]
+fn App() -> impl IntoView {
+    let demo = RwSignal::new(Demo::Fluids);
+    // shared by whichever demo is showing, the autosave reads its sessions
+    let snapshots = Snapshots::new();
+    session::use_autosave(snapshots);
+
+    // a session left over from the last visit, offered until it's resumed
+    let last_session = RwSignal::new(None);
+    spawn_local(async move {
+        match session::load_session().await {
+            Ok(Some(bytes)) => match Snapshot::decode(&bytes) {
+                Ok(snapshot) => last_session.set(Some((Demo::from_kind(snapshot.kind), bytes))),
+                Err(err) => console::log_1(&format!("Ignoring last session: {err}").into()),
+            },
+            Ok(None) => {}
+            Err(err) => console::log_2(&"Failed to read last session".into(), &err),
+        }
+    });
+    let resume = move |_| {
+        if let Some((session_demo, bytes)) = last_session.write().take() {
+            demo.set(session_demo);
+            snapshots.load(bytes);
+        }
+    };
+
+    let tabs = Demo::ALL
+        .iter()
+        .map(|tab| {
+            view! {
+                <button disabled=move || demo.get() == *tab on:click=move |_| demo.set(*tab)>
+                    {tab.label()}
+                </button>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <nav style:margin="40px">
+            {tabs}
+            <Show when=move || last_session.with(Option::is_some)>
+                <button on:click=resume>
+                    {move || {
+                        last_session
+                            .with(|session| {
+                                let label = session.as_ref().map_or("", |(demo, _)| demo.label());
+                                format!("Resume last session ({label})")
+                            })
+                    }}
+                </button>
+            </Show>
+        </nav>
+        {move || match demo.get() {
+            Demo::Fluids => view! { <fluids::App snapshots=snapshots /> }.into_any(),
+            Demo::Life => view! { <life::App snapshots=snapshots /> }.into_any(),
+            Demo::Sand => view! { <sand::App snapshots=snapshots /> }.into_any(),
+        }}
+    }
+}
 
 fn main() {
     console_error_panic_hook::set_once();
-    leptos::mount::mount_to_body(fluids::App);
-}
\ No newline at end of file
+    leptos::mount::mount_to_body(App);
+}
@@ -0,0 +1,239 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use leptos::ev;
+use leptos::html::Canvas;
+use leptos::prelude::*;
+use leptos::wasm_bindgen::JsCast;
+use leptos_use::use_event_listener;
+use serde::{Deserialize, Serialize};
+use web_sys::PointerEvent;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PointerPhase {
+    Down,
+    Move,
+    Up,
+}
+
+// positions are in uv space, (0, 0) is the bottom left of the canvas
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct PointerInput {
+    pub id: i32,
+    pub phase: PointerPhase,
+    pub position: (f32, f32),
+    pub time: f64,
+}
+
+impl PointerInput {
+    fn from_event(evt: &PointerEvent, phase: PointerPhase) -> Self {
+        let size = evt
+            .current_target()
+            .and_then(|target| target.dyn_into::<web_sys::HtmlElement>().ok())
+            .map(|elem| (elem.client_width(), elem.client_height()))
+            .unwrap_or((1, 1));
+        PointerInput {
+            id: evt.pointer_id(),
+            phase,
+            position: to_uv((evt.offset_x(), evt.offset_y()), size),
+            time: evt.time_stamp(),
+        }
+    }
+}
+
+// an offset in css pixels from the top left of an element into uv, a
+// collapsed element counts as one pixel across rather than dividing by zero
+fn to_uv(offset: (i32, i32), (width, height): (i32, i32)) -> (f32, f32) {
+    (
+        offset.0 as f32 / width.max(1) as f32,
+        1.0 - offset.1 as f32 / height.max(1) as f32,
+    )
+}
+
+struct Pointer {
+    position: (f32, f32),
+    // where this pointer was the last time its movement was taken
+    previous: (f32, f32),
+    // uv per millisecond, from the two most recent events
+    velocity: (f32, f32),
+    last_time: f64,
+}
+
+// movement of a single pointer since the last time movements were taken
+#[derive(Clone, Copy)]
+pub struct PointerMove {
+    pub position: (f32, f32),
+    pub velocity: (f32, f32),
+}
+
+#[derive(Default)]
+pub struct Pointers {
+    // ordered so every frame walks the pointers in the same order
+    active: BTreeMap<i32, Pointer>,
+}
+
+impl Pointers {
+    pub fn handle(&mut self, input: PointerInput) {
+        match input.phase {
+            PointerPhase::Down => {
+                self.active.insert(
+                    input.id,
+                    Pointer {
+                        position: input.position,
+                        previous: input.position,
+                        velocity: (0.0, 0.0),
+                        last_time: input.time,
+                    },
+                );
+            }
+            PointerPhase::Move => {
+                if let Some(pointer) = self.active.get_mut(&input.id) {
+                    let elapsed = (input.time - pointer.last_time) as f32;
+                    if elapsed > 0.0 {
+                        pointer.velocity = (
+                            (input.position.0 - pointer.position.0) / elapsed,
+                            (input.position.1 - pointer.position.1) / elapsed,
+                        );
+                    }
+                    pointer.position = input.position;
+                    pointer.last_time = input.time;
+                }
+            }
+            PointerPhase::Up => {
+                self.active.remove(&input.id);
+            }
+        }
+    }
+
+    pub fn take_moves(&mut self) -> Vec<PointerMove> {
+        self.active
+            .values_mut()
+            .filter(|pointer| pointer.position != pointer.previous)
+            .map(|pointer| {
+                pointer.previous = pointer.position;
+                PointerMove {
+                    position: pointer.position,
+                    velocity: pointer.velocity,
+                }
+            })
+            .collect()
+    }
+}
+
+// raw input waiting for the animation loop, which decides what to feed Pointers
+pub type PointerQueue = Rc<RefCell<Vec<PointerInput>>>;
+
+fn listen<E>(target: NodeRef<Canvas>, queue: PointerQueue, event: E, phase: PointerPhase)
+where
+    E: ev::EventDescriptor<EventType = PointerEvent> + 'static,
+{
+    let _ = use_event_listener(target, event, move |evt: PointerEvent| {
+        // keep getting moves for this pointer if it leaves the canvas
+        if phase == PointerPhase::Down
+            && let Some(canvas) = target.get_untracked()
+        {
+            let _ = canvas.set_pointer_capture(evt.pointer_id());
+        }
+        queue
+            .borrow_mut()
+            .push(PointerInput::from_event(&evt, phase));
+    });
+}
+
+// pointer events cover mouse, pen and touch alike, so these four listeners
+// catch every press and drag on the canvas
+pub fn use_pointers(target: NodeRef<Canvas>) -> PointerQueue {
+    let queue = PointerQueue::default();
+    listen(target, queue.clone(), ev::pointerdown, PointerPhase::Down);
+    listen(target, queue.clone(), ev::pointermove, PointerPhase::Move);
+    listen(target, queue.clone(), ev::pointerup, PointerPhase::Up);
+    listen(target, queue.clone(), ev::pointercancel, PointerPhase::Up);
+    queue
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(id: i32, phase: PointerPhase, position: (f32, f32), time: f64) -> PointerInput {
+        PointerInput {
+            id,
+            phase,
+            position,
+            time,
+        }
+    }
+
+    #[test]
+    fn offsets_to_uv() {
+        assert_eq!(to_uv((0, 0), (200, 100)), (0.0, 1.0));
+        assert_eq!(to_uv((200, 100), (200, 100)), (1.0, 0.0));
+        assert_eq!(to_uv((50, 25), (200, 100)), (0.25, 0.75));
+        // past the edges while captured
+        assert_eq!(to_uv((-20, 150), (200, 100)), (-0.1, -0.5));
+        assert_eq!(to_uv((3, 0), (0, 0)), (3.0, 1.0));
+    }
+
+    #[test]
+    fn pointers_are_tracked_separately() {
+        let mut pointers = Pointers::default();
+        pointers.handle(input(1, PointerPhase::Down, (0.1, 0.1), 0.0));
+        pointers.handle(input(2, PointerPhase::Down, (0.5, 0.5), 0.0));
+        // presses alone aren't movement
+        assert!(pointers.take_moves().is_empty());
+
+        pointers.handle(input(2, PointerPhase::Move, (0.5, 0.7), 10.0));
+        pointers.handle(input(1, PointerPhase::Move, (0.2, 0.1), 20.0));
+        let moves = pointers.take_moves();
+        // in id order whatever order they moved in
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves[0].position, (0.2, 0.1));
+        assert!((moves[0].velocity.0 - 0.005).abs() < 1e-6);
+        assert_eq!(moves[0].velocity.1, 0.0);
+        assert_eq!(moves[1].position, (0.5, 0.7));
+        assert!((moves[1].velocity.1 - 0.02).abs() < 1e-6);
+        // each movement is only taken once
+        assert!(pointers.take_moves().is_empty());
+
+        pointers.handle(input(1, PointerPhase::Up, (0.2, 0.1), 30.0));
+        pointers.handle(input(2, PointerPhase::Move, (0.6, 0.7), 30.0));
+        let moves = pointers.take_moves();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].position, (0.6, 0.7));
+    }
+
+    #[test]
+    fn moves_need_a_press() {
+        // a mouse hovering over the canvas sends moves with no button down
+        let mut pointers = Pointers::default();
+        pointers.handle(input(1, PointerPhase::Move, (0.3, 0.3), 0.0));
+        assert!(pointers.take_moves().is_empty());
+    }
+
+    #[test]
+    fn cancel_clears_a_pointer() {
+        // pointercancel comes through as Up, say when the browser takes a
+        // touch over for scrolling
+        let mut pointers = Pointers::default();
+        pointers.handle(input(7, PointerPhase::Down, (0.1, 0.1), 0.0));
+        pointers.handle(input(7, PointerPhase::Move, (0.2, 0.2), 5.0));
+        pointers.handle(input(7, PointerPhase::Up, (0.2, 0.2), 6.0));
+        assert!(pointers.take_moves().is_empty());
+        // and a later move with the same id doesn't bring it back
+        pointers.handle(input(7, PointerPhase::Move, (0.4, 0.4), 9.0));
+        assert!(pointers.take_moves().is_empty());
+    }
+
+    #[test]
+    fn same_time_moves_keep_velocity() {
+        let mut pointers = Pointers::default();
+        pointers.handle(input(1, PointerPhase::Down, (0.0, 0.0), 0.0));
+        pointers.handle(input(1, PointerPhase::Move, (0.1, 0.0), 10.0));
+        // coalesced events can share a timestamp, no dividing by zero
+        pointers.handle(input(1, PointerPhase::Move, (0.2, 0.0), 10.0));
+        let moves = pointers.take_moves();
+        assert_eq!(moves[0].position, (0.2, 0.0));
+        assert!((moves[0].velocity.0 - 0.01).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::VecDeque;
+
+use leptos::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use web_sys::console;
+
+use crate::capture::download;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ReplayMode {
+    Live,
+    Recording,
+    Replaying,
+}
+
+// Recording and replay requests shared between a demo's controls and the
+// InputLog in its animation loop, log holds the last recording as JSON
+#[derive(Clone, Copy)]
+pub struct Replay {
+    mode: RwSignal<ReplayMode>,
+    log: RwSignal<String>,
+}
+
+impl Replay {
+    pub fn new() -> Self {
+        Replay {
+            mode: RwSignal::new(ReplayMode::Live),
+            log: RwSignal::new(String::new()),
+        }
+    }
+}
+
+#[component]
+pub fn ReplayControls(replay: Replay, name: &'static str) -> impl IntoView {
+    let mode = replay.mode;
+    view! {
+        <div style:margin="40px">
+            <button
+                disabled=move || mode.get() == ReplayMode::Replaying
+                on:click=move |_| {
+                    mode.update(|mode| {
+                        *mode = match mode {
+                            ReplayMode::Recording => ReplayMode::Live,
+                            _ => ReplayMode::Recording,
+                        }
+                    })
+                }
+            >
+                {move || match mode.get() {
+                    ReplayMode::Recording => "Stop recording input",
+                    _ => "Record input",
+                }}
+            </button>
+            <button
+                disabled=move || mode.get() == ReplayMode::Recording
+                on:click=move |_| {
+                    mode.update(|mode| {
+                        *mode = match mode {
+                            ReplayMode::Replaying => ReplayMode::Live,
+                            _ => ReplayMode::Replaying,
+                        }
+                    })
+                }
+            >
+                {move || match mode.get() {
+                    ReplayMode::Replaying => "Stop replay",
+                    _ => "Replay input",
+                }}
+            </button>
+            <button
+                disabled=move || replay.log.with(String::is_empty)
+                on:click=move |_| {
+                    replay
+                        .log
+                        .with(|log| {
+                            download(log.as_bytes(), &format!("{name}-input.json"), "application/json")
+                        })
+                }
+            >
+                "Download input"
+            </button>
+            <br />
+            <textarea
+                rows="4"
+                cols="80"
+                placeholder="Recorded input shows up here, or paste some in to replay"
+                prop:value=move || replay.log.get()
+                on:change=move |ev| replay.log.set(event_target_value(&ev))
+            />
+        </div>
+    }
+}
+
+// everything fed into the sim on one frame, frames are counted from the
+// start of the recording
+#[derive(Serialize, Deserialize)]
+struct FrameInput<T> {
+    frame: u64,
+    steps: u32,
+    inputs: Vec<T>,
+}
+
+// Sits between a loop's live input and its sim. While recording it keeps
+// every frame's input and step count, while replaying it swaps both out for
+// the recorded ones so the run comes out the same
+pub struct InputLog<T> {
+    replay: Replay,
+    mode: ReplayMode,
+    frame: u64,
+    recorded: Vec<FrameInput<T>>,
+    playback: VecDeque<FrameInput<T>>,
+}
+
+// what the loop should do with this frame
+pub struct LoggedFrame<T> {
+    // the sim has to go back to its initial state before this frame
+    pub restart: bool,
+    pub steps: u32,
+    pub inputs: Vec<T>,
+}
+
+impl<T: Clone + Serialize + DeserializeOwned> InputLog<T> {
+    pub fn new(replay: Replay) -> Self {
+        InputLog {
+            replay,
+            mode: ReplayMode::Live,
+            frame: 0,
+            recorded: Vec::new(),
+            playback: VecDeque::new(),
+        }
+    }
+
+    // playing is false while the sim is paused, which holds a replay in place
+    pub fn frame(&mut self, playing: bool, steps: u32, inputs: Vec<T>) -> LoggedFrame<T> {
+        let restart = self.update_mode();
+        match self.mode {
+            ReplayMode::Live => LoggedFrame {
+                restart,
+                steps,
+                inputs,
+            },
+            ReplayMode::Recording => {
+                if steps > 0 || !inputs.is_empty() {
+                    self.recorded.push(FrameInput {
+                        frame: self.frame,
+                        steps,
+                        inputs: inputs.clone(),
+                    });
+                }
+                self.frame += 1;
+                LoggedFrame {
+                    restart,
+                    steps,
+                    inputs,
+                }
+            }
+            ReplayMode::Replaying if !playing => LoggedFrame {
+                restart,
+                steps: 0,
+                inputs: Vec::new(),
+            },
+            ReplayMode::Replaying => {
+                let logged = match self.playback.front() {
+                    Some(next) if next.frame == self.frame => {
+                        let next = self.playback.pop_front().unwrap();
+                        LoggedFrame {
+                            restart,
+                            steps: next.steps,
+                            inputs: next.inputs,
+                        }
+                    }
+                    _ => LoggedFrame {
+                        restart,
+                        steps: 0,
+                        inputs: Vec::new(),
+                    },
+                };
+                self.frame += 1;
+                if self.playback.is_empty() {
+                    self.replay.mode.set(ReplayMode::Live);
+                }
+                logged
+            }
+        }
+    }
+
+    // true when a recording or replay has just started
+    fn update_mode(&mut self) -> bool {
+        let mode = self.replay.mode.get_untracked();
+        if mode == self.mode {
+            return false;
+        }
+
+        if self.mode == ReplayMode::Recording {
+            match serde_json::to_string(&self.recorded) {
+                Ok(json) => self.replay.log.set(json),
+                Err(err) => console::log_1(&format!("Failed to save input: {err}").into()),
+            }
+            self.recorded.clear();
+        }
+        self.mode = mode;
+        self.frame = 0;
+
+        match mode {
+            ReplayMode::Live => false,
+            ReplayMode::Recording => true,
+            ReplayMode::Replaying => {
+                let parsed = self
+                    .replay
+                    .log
+                    .with_untracked(|log| serde_json::from_str::<VecDeque<FrameInput<T>>>(log));
+                match parsed {
+                    Ok(playback) => {
+                        self.playback = playback;
+                        true
+                    }
+                    Err(err) => {
+                        console::log_1(&format!("Failed to read input: {err}").into());
+                        self.mode = ReplayMode::Live;
+                        self.replay.mode.set(ReplayMode::Live);
+                        false
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,7 +1,6 @@
 use std::cell::RefCell;
 use std::convert::Infallible;
 use std::f64::consts::PI;
-use std::ops::Div;
 use std::rc::Rc;
 
 use utility::prelude::*;
@@ -12,32 +11,70 @@ use leptos::html::Canvas;
 use leptos::prelude::*;
 use leptos::wasm_bindgen::prelude::*;
 
-use leptos::logging::log;
-
 use leptos_use::use_event_listener;
+use serde::{Deserialize, Serialize};
+
+use web_sys::{WebGl2RenderingContext, console};
 
-use web_sys::WebGl2RenderingContext;
+use crate::capture::{Capture, CaptureControls, Recorder};
+use crate::replay::{InputLog, Replay, ReplayControls};
+use crate::snapshot::{
+    DemoKind, Snapshot, SnapshotControls, Snapshots, TextureData, TextureFormat,
+};
+use crate::transport::{SimClock, Transport, own_loop};
 
 type GL = WebGl2RenderingContext;
 
-render_pipeline!(AvalanchePipeline, "shaders/avalanche.frag");
+// simulated time covered by one step of the light
+const STEP_MS: f64 = 1000.0 / 60.0;
+
+const SAND_PARAMS: [(u32, u32); 4] = [
+    (GL::TEXTURE_MIN_FILTER, GL::NEAREST),
+    (GL::TEXTURE_MAG_FILTER, GL::NEAREST),
+    (GL::TEXTURE_WRAP_S, GL::REPEAT),
+    (GL::TEXTURE_WRAP_T, GL::REPEAT),
+];
 
 render_pipeline!(DropPipeline, "shaders/drop_sand.frag");
 
 render_pipeline!(ShadowPipeline, "shaders/shadow.frag");
 
+// a click on the canvas, in uv space with (0, 0) at the bottom left
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct SandDrop {
+    position: (f32, f32),
+}
+
+// settings saved alongside the sand in a snapshot
+#[derive(Serialize, Deserialize)]
+struct SandConfig {
+    light_time: f64,
+}
+
 #[component]
-pub fn App() -> impl IntoView {
+pub fn App(snapshots: Snapshots) -> impl IntoView {
     let canvas_ref = NodeRef::<Canvas>::new();
-    let (mouse, set_mouse) = signal((0usize, 0i32, 0i32));
-    let _ = use_event_listener(canvas_ref, leptos::ev::click, move |evt| {
-        set_mouse.update(|tup| {
-            tup.0 += 1;
-            tup.1 = evt.offset_x();
-            tup.2 = evt.offset_y();
+    // clicks waiting for the animation loop to drop sand at them
+    let drops = Rc::new(RefCell::new(Vec::new()));
+    {
+        let drops = drops.clone();
+        let _ = use_event_listener(canvas_ref, leptos::ev::click, move |evt| {
+            let (width, height) = evt
+                .current_target()
+                .and_then(|target| target.dyn_into::<web_sys::HtmlElement>().ok())
+                .map(|elem| (elem.client_width().max(1), elem.client_height().max(1)))
+                .unwrap_or((1, 1));
+            drops.borrow_mut().push(SandDrop {
+                position: (
+                    evt.offset_x() as f32 / width as f32,
+                    1.0 - evt.offset_y() as f32 / height as f32,
+                ),
+            });
         });
-    });
-    let (count, set_count) = signal(0);
+    }
+    let clock = SimClock::new();
+    let capture = Capture::new();
+    let replay = Replay::new();
     Effect::new(move |_| {
         if let Some(canvas) = canvas_ref.get() {
             canvas.set_width(512);
@@ -48,22 +85,33 @@ pub fn App() -> impl IntoView {
                 .expect("object")
                 .dyn_into::<WebGl2RenderingContext>()
                 .unwrap();
-            canvas_fill(context.clone(), count.into(), mouse.into());
+            canvas_fill(
+                context.clone(),
+                clock,
+                capture,
+                replay,
+                snapshots,
+                drops.clone(),
+            );
         }
     });
 
     view! {
-    <button
-        on:click=move |_| *set_count.write() += 1
-    >
-        {move || {if count.get() % 2 == 0 {"STOP"} else {"START"}}}
-    </button> <canvas node_ref=canvas_ref /> }
+        <Transport clock=clock />
+        <CaptureControls capture=capture />
+        <ReplayControls replay=replay name="sand" />
+        <SnapshotControls snapshots=snapshots />
+        <canvas node_ref=canvas_ref />
+    }
 }
 
 fn canvas_fill(
     context: WebGl2RenderingContext,
-    count: Signal<i32>,
-    mouse: Signal<(usize, i32, i32)>,
+    clock: SimClock,
+    capture: Capture,
+    replay: Replay,
+    snapshots: Snapshots,
+    drops: Rc<RefCell<Vec<SandDrop>>>,
 ) {
     let quad_vert_shader = compile_shader(
         &context,
@@ -72,20 +120,6 @@ fn canvas_fill(
     )
     .unwrap();
 
-    let quad_frag_shader = compile_shader(
-        &context,
-        GL::FRAGMENT_SHADER,
-        include_str!("shaders/quad.frag"),
-    )
-    .unwrap();
-
-    let avalanche_frag_shader = compile_shader(
-        &context,
-        GL::FRAGMENT_SHADER,
-        include_str!("shaders/avalanche.frag"),
-    )
-    .unwrap();
-
     let shadow_frag_shader = compile_shader(
         &context,
         GL::FRAGMENT_SHADER,
@@ -108,61 +142,70 @@ fn canvas_fill(
 
     let window_texel_size = (1.0 / window_w as f32, 1.0 / window_h as f32);
 
-    let quad_program = Program::create(&context, &quad_vert_shader, &quad_frag_shader);
-    let avalanche_program = Program::create(&context, &quad_vert_shader, &avalanche_frag_shader);
     let shadow_program = Program::create(&context, &quad_vert_shader, &shadow_frag_shader);
     let drop_program = Program::create(&context, &quad_vert_shader, &drop_frag_shader);
 
-    let mut avalanche_pipeline = AvalanchePipeline::create(&context, avalanche_program);
     let mut shadow_pipeline = ShadowPipeline::create(&context, shadow_program);
     let mut drop_pipeline = DropPipeline::create(&context, drop_program);
 
-    let mut sand = Rc::new(RefCell::new(make_sand(&context, sand_w, sand_h)));
-
-    let (next_fame, set_next_frame) = signal(());
-
-    request_animation_frame(move || {
-        *set_next_frame.write();
-    });
+    let mut sand = make_sand(&context, sand_w, sand_h);
+
+    let mut ticks = FixedTimestep::new(STEP_MS, 4);
+    let mut light_time = 0.0;
+    let mut recorder = Recorder::new(capture, "sand");
+    let mut input_log = InputLog::new(replay);
+
+    let quad = Quad::create(&context);
+
+    own_loop(AnimationLoop::start(move |frame| {
+        let steps = if capture.is_recording() {
+            1
+        } else {
+            ticks.advance(clock.advance(frame.dt)) + clock.take_step() as u32
+        };
+
+        // clicks go through the log so they can be recorded or replaced
+        let clicks = std::mem::take(&mut *drops.borrow_mut());
+        let logged = input_log.frame(clock.is_playing(), steps, clicks);
+        let steps = logged.steps;
+        if logged.restart {
+            sand = make_sand(&context, sand_w, sand_h);
+            ticks = FixedTimestep::new(STEP_MS, 4);
+            light_time = 0.0;
+        }
 
-    let mut prev_time = None::<f64>;
-    let mut angle = 0.0;
+        if let Some(bytes) = snapshots.take_load() {
+            match Snapshot::decode(&bytes)
+                .map_err(|err| err.to_string())
+                .and_then(|snapshot| load_sand(&context, &snapshot, sand_w, sand_h))
+            {
+                Ok((loaded, config)) => {
+                    sand = loaded;
+                    light_time = config.light_time;
+                }
+                Err(err) => console::log_1(&format!("Failed to load snapshot: {err}").into()),
+            }
+        }
 
-    let quad = Rc::new(Quad::create(&context));
-    {
-        let context = context.clone();
-        let sand = sand.clone();
-        let quad = quad.clone();
-        Effect::new(move || {
-            let (_, mouse_x, mouse_y) = mouse.get();
-            let pos: (f32, f32) = (
-                mouse_x as f32 / window_w as f32,
-                1.0 - mouse_y as f32 / window_h as f32,
-            );
+        for click in logged.inputs {
             drop_pipeline.set_arguments(
                 &context,
-                sand.borrow().read(),
-                sand.borrow().read().texel_size(),
+                sand.read(),
+                sand.read().texel_size(),
                 255.0,
                 0.6,
-                pos,
+                click.position,
             );
-            quad.blit(Some(&sand.borrow().write()));
-            sand.borrow_mut().swap();
-        });
-    }
-
-    Effect::new(move || {
-        next_fame.get();
-        let now = window().performance().unwrap().now();
-
-        if count.get() % 2 == 0 {
-            angle = now % 20000.0 * (PI / 10000.0);
+            quad.blit(Some(sand.write()));
+            sand.swap();
         }
 
+        light_time += steps as f64 * STEP_MS;
+        let angle = light_time % 20000.0 * (PI / 10000.0);
+
         shadow_pipeline.set_arguments(
             &context,
-            sand.borrow().read(),
+            sand.read(),
             window_texel_size,
             (angle.cos() as f32, angle.sin() as f32),
             30f32.to_radians().tan(),
@@ -170,14 +213,56 @@ fn canvas_fill(
         );
         quad.blit(None);
 
-        request_animation_frame(move || {
-            *set_next_frame.write();
-        });
-    });
+        recorder.capture(&context, STEP_MS);
+
+        if snapshots.take_save() {
+            snapshots.save(&save_sand(&sand, light_time), "sand");
+        }
+        if snapshots.session_due() {
+            snapshots.store_session(&save_sand(&sand, light_time));
+        }
+    }));
+}
+
+fn save_sand(sand: &SwappableTexture, light_time: f64) -> Snapshot {
+    Snapshot {
+        kind: DemoKind::Sand,
+        config: serde_json::to_string(&SandConfig { light_time }).expect("failed to write config"),
+        textures: vec![TextureData::from_rgba_u8(
+            "sand",
+            sand.read().width(),
+            sand.read().height(),
+            TextureFormat::R8,
+            &sand.read().read_rgba_u8(),
+        )],
+    }
+}
+
+fn load_sand(
+    context: &WebGl2RenderingContext,
+    snapshot: &Snapshot,
+    width: usize,
+    height: usize,
+) -> Result<(SwappableTexture, SandConfig), String> {
+    if snapshot.kind != DemoKind::Sand {
+        return Err("snapshot is from a different demo".to_string());
+    }
+    let config = snapshot
+        .config::<SandConfig>()
+        .map_err(|err| err.to_string())?;
+    let texture = snapshot
+        .texture("sand")
+        .filter(|sand| {
+            sand.format == TextureFormat::R8
+                && (sand.width as usize, sand.height as usize) == (width, height)
+        })
+        .ok_or("sand is missing or doesn't match the sim")?;
+    let sand = texture.create_texture(context, &SAND_PARAMS);
+    Ok((sand, config))
 }
 
 fn make_sand(context: &WebGl2RenderingContext, width: usize, height: usize) -> SwappableTexture {
-    return SwappableTexture::create(
+    SwappableTexture::create(
         context,
         GL::TEXTURE_2D,
         0,
@@ -188,11 +273,6 @@ fn make_sand(context: &WebGl2RenderingContext, width: usize, height: usize) -> S
         GL::RED,
         GL::UNSIGNED_BYTE,
         None::<Infallible>,
-        &[
-            (GL::TEXTURE_MIN_FILTER, GL::NEAREST),
-            (GL::TEXTURE_MAG_FILTER, GL::NEAREST),
-            (GL::TEXTURE_WRAP_S, GL::REPEAT),
-            (GL::TEXTURE_WRAP_T, GL::REPEAT),
-        ],
-    );
+        &SAND_PARAMS,
+    )
 }
@@ -0,0 +1,160 @@
+use std::f64::consts::TAU;
+
+use crate::pointers::{PointerInput, PointerPhase, Pointers};
+
+// A list of pointer events to play back instead of live input, times are
+// milliseconds of simulated time from the start of the run
+pub struct PointerScript {
+    events: Vec<PointerInput>,
+    next: usize,
+}
+
+impl PointerScript {
+    pub fn parse(json: &str) -> serde_json::Result<Self> {
+        let mut events: Vec<PointerInput> = serde_json::from_str(json)?;
+        // stable so events sharing a time keep the order they were written in
+        events.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(PointerScript { events, next: 0 })
+    }
+
+    // feeds every event up to and including time into pointers
+    pub fn play_until(&mut self, time: f64, pointers: &mut Pointers) {
+        while let Some(event) = self.events.get(self.next)
+            && event.time <= time
+        {
+            pointers.handle(*event);
+            self.next += 1;
+        }
+    }
+}
+
+// one pointer tracing a figure of eight around the middle of the canvas
+pub fn figure_eight(duration_ms: f64, interval_ms: f64) -> Vec<PointerInput> {
+    let samples = (duration_ms / interval_ms) as usize;
+    let point = |idx: usize, phase| {
+        let t = idx as f64 / samples as f64 * TAU;
+        PointerInput {
+            id: 0,
+            phase,
+            position: (
+                (0.5 + 0.3 * t.sin()) as f32,
+                (0.5 + 0.2 * (2.0 * t).sin()) as f32,
+            ),
+            time: idx as f64 * interval_ms,
+        }
+    };
+    std::iter::once(point(0, PointerPhase::Down))
+        .chain((1..samples).map(|idx| point(idx, PointerPhase::Move)))
+        .chain(std::iter::once(point(samples, PointerPhase::Up)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: i32, phase: &str, x: f32, time: f64) -> String {
+        format!(r#"{{"id":{id},"phase":"{phase}","position":[{x},0.5],"time":{time}}}"#)
+    }
+
+    fn script(events: &[String]) -> PointerScript {
+        PointerScript::parse(&format!("[{}]", events.join(","))).unwrap()
+    }
+
+    fn positions(pointers: &mut Pointers) -> Vec<f32> {
+        pointers
+            .take_moves()
+            .iter()
+            .map(|pointer| pointer.position.0)
+            .collect()
+    }
+
+    #[test]
+    fn plays_up_to_time() {
+        let mut script = script(&[
+            event(0, "Down", 0.1, 0.0),
+            event(0, "Move", 0.2, 10.0),
+            event(0, "Move", 0.3, 20.0),
+        ]);
+        let mut pointers = Pointers::default();
+        script.play_until(5.0, &mut pointers);
+        assert!(positions(&mut pointers).is_empty());
+        // inclusive of events exactly at the time
+        script.play_until(10.0, &mut pointers);
+        assert_eq!(positions(&mut pointers), [0.2]);
+        script.play_until(15.0, &mut pointers);
+        assert!(positions(&mut pointers).is_empty());
+        script.play_until(100.0, &mut pointers);
+        assert_eq!(positions(&mut pointers), [0.3]);
+    }
+
+    #[test]
+    fn sorted_by_time() {
+        let mut script = script(&[
+            event(0, "Move", 0.3, 20.0),
+            event(0, "Down", 0.1, 0.0),
+            event(0, "Move", 0.2, 10.0),
+        ]);
+        let mut pointers = Pointers::default();
+        script.play_until(10.0, &mut pointers);
+        assert_eq!(positions(&mut pointers), [0.2]);
+    }
+
+    #[test]
+    fn ties_keep_written_order() {
+        // the move comes after the down in the file, so the pointer exists
+        let mut script = script(&[
+            event(0, "Down", 0.1, 5.0),
+            event(0, "Move", 0.2, 5.0),
+            event(1, "Down", 0.6, 0.0),
+        ]);
+        let mut pointers = Pointers::default();
+        script.play_until(5.0, &mut pointers);
+        assert_eq!(positions(&mut pointers), [0.2]);
+    }
+
+    #[test]
+    fn up_ends_pointer() {
+        let mut script = script(&[
+            event(0, "Down", 0.1, 0.0),
+            event(0, "Up", 0.1, 10.0),
+            event(0, "Move", 0.4, 20.0),
+        ]);
+        let mut pointers = Pointers::default();
+        script.play_until(20.0, &mut pointers);
+        assert!(positions(&mut pointers).is_empty());
+    }
+
+    #[test]
+    fn bad_scripts() {
+        assert!(PointerScript::parse("").is_err());
+        assert!(PointerScript::parse("{}").is_err());
+        assert!(PointerScript::parse(&format!("[{}]", event(0, "Hover", 0.1, 0.0))).is_err());
+        assert!(PointerScript::parse("[]").is_ok());
+    }
+
+    #[test]
+    fn figure_eight_shape() {
+        let events = figure_eight(1000.0, 100.0);
+        assert_eq!(events.len(), 11);
+        assert!(events[0].phase == PointerPhase::Down);
+        assert!(events[1..10].iter().all(|e| e.phase == PointerPhase::Move));
+        assert!(events[10].phase == PointerPhase::Up);
+        assert!(events.windows(2).all(|pair| pair[0].time < pair[1].time));
+        assert!(events.iter().all(|e| {
+            (0.0..=1.0).contains(&e.position.0) && (0.0..=1.0).contains(&e.position.1)
+        }));
+        // a full loop ends back where it started
+        let (start, end) = (events[0].position, events[10].position);
+        assert!((start.0 - end.0).abs() < 1e-5 && (start.1 - end.1).abs() < 1e-5);
+    }
+
+    #[test]
+    fn figure_eight_round_trips() {
+        let json = serde_json::to_string(&figure_eight(1000.0, 100.0)).unwrap();
+        let mut script = PointerScript::parse(&json).unwrap();
+        let mut pointers = Pointers::default();
+        script.play_until(400.0, &mut pointers);
+        assert_eq!(pointers.take_moves().len(), 1);
+    }
+}
@@ -0,0 +1,108 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos::wasm_bindgen::{JsCast, JsValue, closure::Closure};
+
+use leptos_use::use_event_listener;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode, console};
+
+use crate::snapshot::Snapshots;
+
+const DATABASE: &str = "jacks_crab_shack";
+const STORE: &str = "sessions";
+const LAST_SESSION: &str = "last";
+
+// how often the running demo's session gets written out
+const AUTOSAVE_MS: u64 = 5000;
+
+// Keeps the running demo's session in IndexedDB. Every AUTOSAVE_MS the last
+// session the demo stored gets written out and a fresh one is asked for, the
+// page going hidden writes out whatever is there right away since animation
+// frames stop once it is. Nothing is written until the page has been pressed
+// or typed in, so just opening it can't replace the last visit's session
+pub fn use_autosave(snapshots: Snapshots) {
+    let interacted = StoredValue::new(false);
+    let _ = use_event_listener(document(), leptos::ev::pointerdown, move |_| {
+        interacted.set_value(true)
+    });
+    let _ = use_event_listener(document(), leptos::ev::keydown, move |_| {
+        interacted.set_value(true)
+    });
+    let write_out = move || {
+        if let Some(bytes) = snapshots.take_session() {
+            spawn_local(async move {
+                if let Err(err) = save_session(bytes).await {
+                    console::log_2(&"Failed to save session".into(), &err);
+                }
+            });
+        }
+    };
+    set_interval(
+        move || {
+            write_out();
+            // with no session asked for there's never anything to write
+            if interacted.get_value() {
+                snapshots.request_session();
+            }
+        },
+        std::time::Duration::from_millis(AUTOSAVE_MS),
+    );
+    let _ = use_event_listener(document(), leptos::ev::visibilitychange, move |_| {
+        if document().hidden() {
+            write_out();
+        }
+    });
+}
+
+// the session saved by the last visit, if there was one
+pub async fn load_session() -> Result<Option<Vec<u8>>, JsValue> {
+    let store = open_store(IdbTransactionMode::Readonly).await?;
+    let value = finished(&store.get(&LAST_SESSION.into())?).await?;
+    if value.is_undefined() {
+        return Ok(None);
+    }
+    Ok(Some(js_sys::Uint8Array::new(&value).to_vec()))
+}
+
+async fn save_session(bytes: Vec<u8>) -> Result<(), JsValue> {
+    let store = open_store(IdbTransactionMode::Readwrite).await?;
+    let value = js_sys::Uint8Array::from(bytes.as_slice());
+    finished(&store.put_with_key(&value, &LAST_SESSION.into())?).await?;
+    Ok(())
+}
+
+async fn open_store(mode: IdbTransactionMode) -> Result<IdbObjectStore, JsValue> {
+    let factory = window()
+        .indexed_db()?
+        .ok_or_else(|| JsValue::from_str("IndexedDB isn't available"))?;
+    let request = factory.open_with_u32(DATABASE, 1)?;
+    // only runs the first time the database gets made
+    let upgrading = request.clone();
+    let upgrade = Closure::once_into_js(move || {
+        if let Ok(database) = upgrading
+            .result()
+            .map(JsCast::unchecked_into::<IdbDatabase>)
+            && let Err(err) = database.create_object_store(STORE)
+        {
+            console::log_2(&"Failed to make session store".into(), &err);
+        }
+    });
+    request.set_onupgradeneeded(Some(upgrade.unchecked_ref()));
+
+    let database = finished(&request).await?.unchecked_into::<IdbDatabase>();
+    database
+        .transaction_with_str_and_mode(STORE, mode)?
+        .object_store(STORE)
+}
+
+// waits for a request to succeed and gives back its result
+async fn finished(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+    JsFuture::from(promise)
+        .await
+        .map_err(|err| request.error().ok().flatten().map_or(err, JsValue::from))?;
+    request.result()
+}
@@ -0,0 +1,23 @@
+attribute vec2 a_position;
+attribute vec2 a_instance;
+
+uniform sampler2D u_velocity;
+uniform float u_scale;
+uniform float u_max_length;
+
+varying float v_alpha;
+
+// a_position is the arrow's outline pointing along +x with unit length,
+// a_instance is the lattice point the arrow starts from
+void main() {
+    vec2 velocity = texture2D(u_velocity, a_instance).xy;
+    float speed = length(velocity);
+    vec2 dir = speed > 0.0 ? velocity / speed : vec2(1.0, 0.0);
+    float len = min(speed * u_scale, u_max_length);
+    vec2 offset = len * vec2(
+        dir.x * a_position.x - dir.y * a_position.y,
+        dir.y * a_position.x + dir.x * a_position.y
+    );
+    gl_Position = vec4((a_instance + offset) * 2.0 - 1.0, 0.0, 1.0);
+    v_alpha = 0.9;
+}
\ No newline at end of file
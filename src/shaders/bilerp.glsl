@@ -0,0 +1,15 @@
+// bilinear filtering done by hand, float textures can't be filtered
+// in hardware everywhere
+vec4 tex_bilerp(in sampler2D tex, in vec2 uv, in vec2 tsize) {
+    vec2 st = uv / tsize - 0.5;
+
+    vec2 iuv = floor(st);
+    vec2 fuv = fract(st);
+
+    vec4 a = texture2D(tex, (iuv + vec2(0.5, 0.5)) * tsize);
+    vec4 b = texture2D(tex, (iuv + vec2(1.5, 0.5)) * tsize);
+    vec4 c = texture2D(tex, (iuv + vec2(0.5, 1.5)) * tsize);
+    vec4 d = texture2D(tex, (iuv + vec2(1.5, 1.5)) * tsize);
+
+    return mix(mix(a, b, fuv.x), mix(c, d, fuv.x), fuv.y);
+}
\ No newline at end of file
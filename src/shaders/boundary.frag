@@ -5,13 +5,25 @@ varying vec2 v_texcoord;
 uniform sampler2D u_target;
 uniform sampler2D u_boundary_offsets;
 uniform vec2 u_texel_size;
-uniform float u_scale;
+// x scales what domain edge cells copy, y what obstacle edge cells copy,
+// and z scales solid interiors in place
+uniform vec3 u_scale;
 
+// must match SOLID_INTERIOR in boundary.rs
+const vec2 SOLID_INTERIOR = vec2(2.0, 2.0);
 
 void main()
 {
-    float scale = u_scale;
-    vec2 offset = texture2D(u_boundary_offsets, v_texcoord).rg * u_texel_size;
+    vec3 texel = texture2D(u_boundary_offsets, v_texcoord).rgb;
+    vec2 texels = texel.xy;
+    if (texels == SOLID_INTERIOR) {
+        gl_FragColor = u_scale.z * texture2D(u_target, v_texcoord);
+        return;
+    }
+
+    // obstacles are flagged in the third channel
+    float scale = texel.z > 0.5 ? u_scale.y : u_scale.x;
+    vec2 offset = texels * u_texel_size;
 
     // don't scale if there's no boundary offset
     if (offset == vec2(0,0)) {
@@ -0,0 +1,24 @@
+precision highp float;
+
+varying vec2 v_texcoord;
+
+uniform sampler2D u_board;
+uniform vec2 u_board_size;
+// the segment the brush swept along in cells, from xy to zw
+uniform vec4 u_segment;
+uniform float u_radius;
+// 1 draws live cells, 0 erases them
+uniform float u_value;
+
+void main() {
+    vec2 cell = floor(v_texcoord * u_board_size) + 0.5;
+    vec2 from = u_segment.xy;
+    vec2 along = u_segment.zw - from;
+    float len2 = dot(along, along);
+    float t = len2 > 0.0 ? clamp(dot(cell - from, along) / len2, 0.0, 1.0) : 0.0;
+    if (length(cell - (from + t * along)) <= u_radius) {
+        gl_FragColor = u_value > 0.5 ? vec4(1,1,1,0) : vec4(0,0,0,1);
+    } else {
+        gl_FragColor = texture2D(u_board, v_texcoord);
+    }
+}
\ No newline at end of file
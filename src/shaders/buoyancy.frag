@@ -0,0 +1,19 @@
+precision highp float;
+
+varying vec2 v_texcoord;
+
+uniform sampler2D u_velocity;
+uniform sampler2D u_smoke;
+// x is the ambient temperature, y how strongly heat lifts and z how
+// strongly smoke weighs the fluid down
+uniform vec3 u_forces;
+uniform float u_timestep;
+
+// hot fluid rises and dense smoke sinks, temperature is in the red
+// channel of the smoke texture and density in the green
+void main() {
+    vec2 smoke = texture2D(u_smoke, v_texcoord).rg;
+    float lift = u_forces.y * (smoke.r - u_forces.x) - u_forces.z * smoke.g;
+    vec4 velocity = texture2D(u_velocity, v_texcoord);
+    gl_FragColor = vec4(velocity.xy + vec2(0.0, u_timestep * lift), velocity.zw);
+}
\ No newline at end of file
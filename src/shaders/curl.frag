@@ -0,0 +1,16 @@
+precision highp float;
+
+varying vec2 v_texcoord;
+
+uniform sampler2D u_velocity;
+uniform vec2 u_texel_size;
+
+void main()
+{
+    float v_l = texture2D(u_velocity, v_texcoord - vec2(u_texel_size.x, 0)).y;
+    float v_r = texture2D(u_velocity, v_texcoord + vec2(u_texel_size.x, 0)).y;
+    float u_b = texture2D(u_velocity, v_texcoord - vec2(0, u_texel_size.y)).x;
+    float u_t = texture2D(u_velocity, v_texcoord + vec2(0, u_texel_size.y)).x;
+
+    gl_FragColor = vec4(0.5 * ((v_r - v_l) - (u_t - u_b)), 0, 0, 0);
+}
\ No newline at end of file
@@ -0,0 +1,50 @@
+precision highp float;
+
+varying vec2 v_texcoord;
+
+uniform sampler2D u_previous;
+uniform sampler2D u_current;
+uniform vec2 u_texel_size;
+uniform float u_alpha;
+
+// catmull-rom weights for the four texels around a sample
+vec4 cubic_weights(float f) {
+    return vec4(
+        f * (-0.5 + f * (1.0 - 0.5 * f)),
+        1.0 + f * f * (-2.5 + 1.5 * f),
+        f * (0.5 + f * (2.0 - 1.5 * f)),
+        f * f * (-0.5 + 0.5 * f)
+    );
+}
+
+vec4 tex_bicubic(in sampler2D tex, in vec2 uv, in vec2 tsize) {
+    vec2 st = uv / tsize - 0.5;
+
+    vec2 iuv = floor(st);
+    vec2 fuv = fract(st);
+
+    vec4 wx = cubic_weights(fuv.x);
+    vec4 wy = cubic_weights(fuv.y);
+
+    vec4 result = vec4(0.0);
+    for (int j = 0; j < 4; j++) {
+        vec4 row = vec4(0.0);
+        for (int i = 0; i < 4; i++) {
+            vec2 texel = iuv + vec2(float(i) - 0.5, float(j) - 0.5);
+            row += wx[i] * texture2D(tex, texel * tsize);
+        }
+        result += wy[j] * row;
+    }
+    // the cubic can overshoot below zero next to sharp edges
+    return max(result, 0.0);
+}
+
+// draws the dye part way between the last two steps, upsampled from the
+// dye resolution to whatever size the target is
+void main() {
+    gl_FragColor = mix(
+        tex_bicubic(u_previous, v_texcoord, u_texel_size),
+        tex_bicubic(u_current, v_texcoord, u_texel_size),
+        u_alpha
+    );
+}
\ No newline at end of file
@@ -0,0 +1,42 @@
+precision highp float;
+
+varying vec2 v_texcoord;
+
+uniform sampler2D u_field;
+uniform float u_range;
+// 0 for the diverging map, 1 for viridis, matches Colormap in display.rs
+uniform float u_colormap;
+
+const vec3 COLD = vec3(0.230, 0.299, 0.754);
+const vec3 NEUTRAL = vec3(0.865, 0.865, 0.865);
+const vec3 HOT = vec3(0.706, 0.016, 0.150);
+
+vec3 diverging(float t) {
+    if (t < 0.5) {
+        return mix(COLD, NEUTRAL, t * 2.0);
+    }
+    return mix(NEUTRAL, HOT, t * 2.0 - 1.0);
+}
+
+// polynomial fit of matplotlib's viridis
+vec3 viridis(float t) {
+    const vec3 c0 = vec3(0.2777273272234177, 0.005407344544966578, 0.3340998053353061);
+    const vec3 c1 = vec3(0.1050930431085774, 1.404613529898575, 1.384590162594685);
+    const vec3 c2 = vec3(-0.3308618287255563, 0.214847559468213, 0.09509516302823659);
+    const vec3 c3 = vec3(-4.634230498983486, -5.799100973351585, -19.33244095627987);
+    const vec3 c4 = vec3(6.228269936347081, 14.17993336680509, 56.69055260068105);
+    const vec3 c5 = vec3(4.776384997670288, -13.74514537774601, -65.35303263337234);
+    const vec3 c6 = vec3(-5.435455855934631, 4.645852612178535, 26.3124352495832);
+    return c0 + t * (c1 + t * (c2 + t * (c3 + t * (c4 + t * (c5 + t * c6)))));
+}
+
+void main() {
+    float value = texture2D(u_field, v_texcoord).r;
+    // zero always lands in the middle of the map
+    float t = clamp(0.5 + 0.5 * value / u_range, 0.0, 1.0);
+    if (u_colormap < 0.5) {
+        gl_FragColor = vec4(diverging(t), 1.0);
+    } else {
+        gl_FragColor = vec4(viridis(t), 1.0);
+    }
+}
\ No newline at end of file
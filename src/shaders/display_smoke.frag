@@ -0,0 +1,16 @@
+precision highp float;
+
+varying vec2 v_texcoord;
+
+uniform sampler2D u_smoke;
+uniform vec2 u_texel_size;
+
+// grey smoke by density with anything that's been heated glowing
+// through red and orange to yellow
+void main() {
+    vec2 smoke = tex_bilerp(u_smoke, v_texcoord, u_texel_size).rg;
+    float heat = clamp(smoke.r, 0.0, 1.0);
+    vec3 fire = vec3(heat * 1.5, heat * heat, heat * heat * heat * 0.5);
+    vec3 color = vec3(clamp(smoke.g, 0.0, 1.0) * 0.8) + fire;
+    gl_FragColor = vec4(clamp(color, 0.0, 1.0), 1.0);
+}
\ No newline at end of file
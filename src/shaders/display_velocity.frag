@@ -0,0 +1,19 @@
+precision highp float;
+
+varying vec2 v_texcoord;
+
+uniform sampler2D u_velocity;
+uniform float u_range;
+
+vec3 hsv_to_rgb(vec3 c) {
+    vec3 p = abs(fract(c.xxx + vec3(1.0, 2.0 / 3.0, 1.0 / 3.0)) * 6.0 - 3.0);
+    return c.z * mix(vec3(1.0), clamp(p - 1.0, 0.0, 1.0), c.y);
+}
+
+// hue shows the direction of the flow and brightness its speed
+void main() {
+    vec2 velocity = texture2D(u_velocity, v_texcoord).xy;
+    float hue = atan(velocity.y, velocity.x) / 6.283185307 + 0.5;
+    float magnitude = clamp(length(velocity) / u_range, 0.0, 1.0);
+    gl_FragColor = vec4(hsv_to_rgb(vec3(hue, 1.0, magnitude)), 1.0);
+}
\ No newline at end of file
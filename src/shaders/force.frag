@@ -1,22 +1,32 @@
-precision mediump float;
+precision highp float;
 
 varying vec2 v_texcoord;
 
 uniform sampler2D u_velocity;
-uniform vec2 u_location;
-uniform vec2 u_direction;
+uniform sampler2D u_impulses;
+uniform float u_count;
 uniform float u_scale;
 uniform float u_radius;
 
+// must match MAX_IMPULSES in fluids.rs
+const int MAX_IMPULSES = 16;
 
 void main()
 {
-    float dist = distance(u_location, v_texcoord);
-    if (dist < u_radius) {
-        gl_FragColor =
-            texture2D(u_velocity, v_texcoord) +
-            vec4(u_direction * u_scale * ((u_radius - dist)/u_radius), 0, 0);
-    } else {
-        gl_FragColor = texture2D(u_velocity, v_texcoord);
+    vec4 result = texture2D(u_velocity, v_texcoord);
+    for (int i = 0; i < MAX_IMPULSES; i++) {
+        if (float(i) >= u_count) {
+            break;
+        }
+        // first row of the impulse texture holds location and direction
+        vec4 impulse = texture2D(
+            u_impulses,
+            vec2((float(i) + 0.5) / float(MAX_IMPULSES), 0.25)
+        );
+        float dist = distance(impulse.xy, v_texcoord);
+        if (dist < u_radius) {
+            result += vec4(impulse.zw * u_scale * ((u_radius - dist)/u_radius), 0, 0);
+        }
     }
+    gl_FragColor = result;
 }
\ No newline at end of file
@@ -1,26 +1,79 @@
-precision mediump float;
+precision highp float;
 
 varying vec2 v_texcoord;
 uniform sampler2D u_texture;
 uniform vec2 u_texel_size;
+// for rules with radius 1, bit n of x is set when n live neighbours bring
+// a dead cell to life and bit n of y when a live cell with n live
+// neighbours stays alive
+uniform vec2 u_masks;
+// inclusive counts for Larger than Life rules, birth in xy and survival in
+// zw, each empty when its min is above its max
+uniform vec4 u_ranges;
+// x is the radius, y the shape (0 Moore, 1 von Neumann, 2 circular), z is
+// 1 when a live cell counts itself and w the number of states, 2 for plain
+// life and each one past that a step of dying
+uniform vec4 u_neighbourhood;
+
+// must match MAX_RADIUS in rule.rs
+const int MAX_RADIUS = 7;
+
+bool has_bit(float mask, float bit) {
+    return bit < 16.0 && mod(floor(mask / exp2(bit)), 2.0) == 1.0;
+}
+
+bool counted(float mask, vec2 range, float count) {
+    return has_bit(mask, count) || (count >= range.x && count <= range.y);
+}
+
+// live cells are red, dying ones keep their state in green
+float state(vec2 uv) {
+    vec4 cell = texture2D(u_texture, uv);
+    return cell.r > 0.0 ? 1.0 : floor(cell.g * 255.0 + 0.5);
+}
+
+// 1 if the cell at offset is one of the neighbours and alive
+float live(vec2 offset) {
+    float radius = u_neighbourhood.x;
+    float shape = u_neighbourhood.y;
+    if (abs(offset.x) > radius || abs(offset.y) > radius) return 0.0;
+    if (shape == 1.0 && abs(offset.x) + abs(offset.y) > radius) return 0.0;
+    if (shape == 2.0 && dot(offset, offset) > radius * radius) return 0.0;
+    if (offset == vec2(0.0) && u_neighbourhood.z == 0.0) return 0.0;
+    return state(v_texcoord + offset * u_texel_size) == 1.0 ? 1.0 : 0.0;
+}
 
 void main() {
-    int sum = 0;
-    bool alive = texture2D(u_texture, v_texcoord).r > 0.0;
-    gl_FragColor = vec4(0,0,0,1);
-    sum += int(texture2D(u_texture, v_texcoord + vec2( 0              , u_texel_size.y  )).r > 0.0);
-    sum += int(texture2D(u_texture, v_texcoord + vec2( u_texel_size.x , u_texel_size.y  )).r > 0.0);
-    sum += int(texture2D(u_texture, v_texcoord + vec2( u_texel_size.x , 0               )).r > 0.0);
-    sum += int(texture2D(u_texture, v_texcoord + vec2( u_texel_size.x , -u_texel_size.y )).r > 0.0);
-    sum += int(texture2D(u_texture, v_texcoord + vec2( 0              , -u_texel_size.y )).r > 0.0);
-    sum += int(texture2D(u_texture, v_texcoord + vec2( -u_texel_size.x, -u_texel_size.y )).r > 0.0);
-    sum += int(texture2D(u_texture, v_texcoord + vec2( -u_texel_size.x, 0               )).r > 0.0);
-    sum += int(texture2D(u_texture, v_texcoord + vec2( -u_texel_size.x, u_texel_size.y  )).r > 0.0);
-    if (alive) {
-        if (sum == 2 || sum == 3) {
-            gl_FragColor = vec4(1,1,1,0);
+    float count = 0.0;
+    // most rules only look at the nearest cells, so don't walk the whole
+    // MAX_RADIUS square for them
+    if (u_neighbourhood.x == 1.0) {
+        for (int y = -1; y <= 1; y++) {
+            for (int x = -1; x <= 1; x++) {
+                count += live(vec2(x, y));
+            }
         }
-    } else if (sum == 3) {
+    } else {
+        for (int y = -MAX_RADIUS; y <= MAX_RADIUS; y++) {
+            for (int x = -MAX_RADIUS; x <= MAX_RADIUS; x++) {
+                count += live(vec2(x, y));
+            }
+        }
+    }
+
+    float current = state(v_texcoord);
+    float next = 0.0;
+    if (current == 0.0) {
+        next = counted(u_masks.x, u_ranges.xy, count) ? 1.0 : 0.0;
+    } else if (current == 1.0 && counted(u_masks.y, u_ranges.zw, count)) {
+        next = 1.0;
+    } else if (current + 1.0 < u_neighbourhood.w) {
+        next = current + 1.0;
+    }
+
+    if (next == 1.0) {
         gl_FragColor = vec4(1,1,1,0);
+    } else {
+        gl_FragColor = vec4(0, next / 255.0, 0, 1);
     }
 }
\ No newline at end of file
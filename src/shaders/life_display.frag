@@ -0,0 +1,33 @@
+precision highp float;
+
+varying vec2 v_texcoord;
+
+uniform sampler2D u_board;
+uniform vec2 u_board_size;
+// board uv at the middle of the canvas
+uniform vec2 u_center;
+// x is the zoom, 1 fits the whole board on the canvas, and y the canvas
+// pixels per cell, grid lines fade in as that grows
+uniform vec2 u_zoom;
+// 2 for plain life, more for rules with dying states
+uniform float u_states;
+
+void main() {
+    // the board repeats so panning past an edge wraps around
+    vec2 uv = fract(u_center + (v_texcoord - 0.5) / u_zoom.x);
+    vec4 cell = texture2D(u_board, uv);
+    vec3 color = vec3(0.0);
+    if (cell.r > 0.0) {
+        color = vec3(1.0);
+    } else if (cell.g > 0.0) {
+        // dying cells cool from orange to a dark red as they age
+        float age = (cell.g * 255.0 - 2.0) / max(u_states - 3.0, 1.0);
+        color = mix(vec3(1.0, 0.6, 0.1), vec3(0.25, 0.0, 0.1), clamp(age, 0.0, 1.0));
+    }
+
+    vec2 edge = fract(uv * u_board_size) * u_zoom.y;
+    float on_line = min(edge.x, edge.y) < 1.0 ? 1.0 : 0.0;
+    float grid = on_line * clamp((u_zoom.y - 6.0) / 10.0, 0.0, 1.0);
+
+    gl_FragColor = vec4(mix(color, vec3(0.3), grid * 0.5), 1.0);
+}
\ No newline at end of file
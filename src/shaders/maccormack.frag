@@ -0,0 +1,37 @@
+precision highp float;
+
+varying vec2 v_texcoord;
+
+uniform sampler2D u_target;
+uniform sampler2D u_backward;
+uniform sampler2D u_velocity;
+// xy is the target's texel size and zw the velocity's
+uniform vec4 u_texel_sizes;
+// x is the timestep and y how quickly the target fades
+uniform vec2 u_step;
+
+// forward is the plain semi-lagrangian result and backward is that result
+// advected back again, half the round trip error is the correction
+void main() {
+    vec2 target_texel_size = u_texel_sizes.xy;
+    vec2 velocity_texel_size = u_texel_sizes.zw;
+    vec2 pos =
+        v_texcoord -
+        u_step.x * velocity_texel_size *
+        tex_bilerp(u_velocity, v_texcoord, velocity_texel_size).rg;
+
+    vec4 forward = tex_bilerp(u_target, pos, target_texel_size);
+    vec4 error = texture2D(u_target, v_texcoord) - texture2D(u_backward, v_texcoord);
+    vec4 result = forward + 0.5 * error;
+
+    // limit to the texels the forward pass interpolated between so the
+    // correction can't overshoot and ring
+    vec2 iuv = floor(pos / target_texel_size - 0.5);
+    vec4 a = texture2D(u_target, (iuv + vec2(0.5, 0.5)) * target_texel_size);
+    vec4 b = texture2D(u_target, (iuv + vec2(1.5, 0.5)) * target_texel_size);
+    vec4 c = texture2D(u_target, (iuv + vec2(0.5, 1.5)) * target_texel_size);
+    vec4 d = texture2D(u_target, (iuv + vec2(1.5, 1.5)) * target_texel_size);
+    result = clamp(result, min(min(a, b), min(c, d)), max(max(a, b), max(c, d)));
+
+    gl_FragColor = vec4(result.rgb / (1.0 + u_step.y * u_step.x), result.a);
+}
\ No newline at end of file
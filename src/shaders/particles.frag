@@ -0,0 +1,9 @@
+precision mediump float;
+
+varying float v_alpha;
+
+void main() {
+    float dist = length(gl_PointCoord - 0.5);
+    float falloff = 1.0 - smoothstep(0.25, 0.5, dist);
+    gl_FragColor = vec4(1.0, 1.0, 1.0, 0.6 * v_alpha * falloff);
+}
\ No newline at end of file
@@ -0,0 +1,15 @@
+attribute vec2 a_position;
+
+uniform sampler2D u_particles;
+uniform float u_point_size;
+
+varying float v_alpha;
+
+// a_position is where this particle lives in the particle texture
+void main() {
+    vec4 particle = texture2D(u_particles, a_position);
+    gl_Position = vec4(particle.xy * 2.0 - 1.0, 0.0, 1.0);
+    gl_PointSize = u_point_size;
+    // fade out as the particle gets older
+    v_alpha = 1.0 - particle.z / particle.w;
+}
\ No newline at end of file
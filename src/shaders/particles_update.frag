@@ -0,0 +1,44 @@
+precision highp float;
+
+varying vec2 v_texcoord;
+
+uniform sampler2D u_particles;
+uniform sampler2D u_velocity;
+// xy is the velocity's texel size, zw is 1 on axes where the flow wraps
+uniform vec4 u_velocity_grid;
+// x is the timestep, y the average lifetime and z a fresh seed every step
+uniform vec3 u_step;
+uniform vec4 u_spawn;
+
+float hash(in vec2 p) {
+    return fract(sin(dot(p, vec2(12.9898, 78.233))) * 43758.5453);
+}
+
+// each texel is one particle: xy position, z age and w lifetime in steps
+void main() {
+    vec2 velocity_texel_size = u_velocity_grid.xy;
+    float seed = u_step.z;
+    vec4 particle = texture2D(u_particles, v_texcoord);
+    float age = particle.z + 1.0;
+
+    if (age >= particle.w) {
+        float r1 = hash(v_texcoord + seed);
+        float r2 = hash(v_texcoord.yx + seed * 1.37);
+        float r3 = hash(v_texcoord * 1.91 + seed);
+        vec2 pos = vec2(r1, r2);
+        // spawn.xy is the centre and spawn.z the radius, w is zero
+        // when nothing is spawning and particles go anywhere
+        if (u_spawn.w > 0.0) {
+            float angle = 6.283185307 * r1;
+            pos = u_spawn.xy + u_spawn.z * sqrt(r2) * vec2(cos(angle), sin(angle));
+        }
+        gl_FragColor = vec4(pos, 0.0, u_step.y * (0.5 + r3));
+        return;
+    }
+
+    vec2 velocity = tex_bilerp(u_velocity, particle.xy, velocity_texel_size).xy;
+    vec2 pos = particle.xy + u_step.x * velocity_texel_size * velocity;
+    // wrap around on axes where the flow is periodic, stop at the edge otherwise
+    pos = mix(clamp(pos, 0.0, 1.0), fract(pos), u_velocity_grid.zw);
+    gl_FragColor = vec4(pos, age, particle.w);
+}
\ No newline at end of file
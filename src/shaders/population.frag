@@ -0,0 +1,26 @@
+precision highp float;
+
+varying vec2 v_texcoord;
+
+// live cells have a red of 1, and each level down holds the sums of the
+// one above in red
+uniform sampler2D u_texture;
+uniform vec2 u_source_size;
+
+// must match BLOCK in population.rs
+const int BLOCK = 4;
+
+void main() {
+    vec2 target_size = ceil(u_source_size / float(BLOCK));
+    vec2 first = floor(v_texcoord * target_size) * float(BLOCK);
+    float sum = 0.0;
+    for (int y = 0; y < BLOCK; y++) {
+        for (int x = 0; x < BLOCK; x++) {
+            vec2 texel = first + vec2(x, y);
+            if (texel.x < u_source_size.x && texel.y < u_source_size.y) {
+                sum += texture2D(u_texture, (texel + 0.5) / u_source_size).r;
+            }
+        }
+    }
+    gl_FragColor = vec4(sum, 0.0, 0.0, 1.0);
+}
\ No newline at end of file
@@ -0,0 +1,38 @@
+precision highp float;
+
+varying vec2 v_texcoord;
+
+uniform sampler2D u_target;
+uniform sampler2D u_impulses;
+// x is how many impulses there are and y their radius
+uniform vec2 u_impulse_shape;
+uniform vec2 u_amount;
+uniform vec4 u_emitter;
+
+// must match MAX_IMPULSES in fluids.rs
+const int MAX_IMPULSES = 16;
+
+// adds heat to the red channel and smoke to the green under every
+// impulse, plus the emitter which is xy centre, z radius and w strength
+void main()
+{
+    float count = u_impulse_shape.x;
+    float radius = u_impulse_shape.y;
+    vec4 result = texture2D(u_target, v_texcoord);
+    for (int i = 0; i < MAX_IMPULSES; i++) {
+        if (float(i) >= count) {
+            break;
+        }
+        float u = (float(i) + 0.5) / float(MAX_IMPULSES);
+        vec2 location = texture2D(u_impulses, vec2(u, 0.25)).xy;
+        float dist = distance(location, v_texcoord);
+        if (dist < radius) {
+            result.rg += u_amount * ((radius - dist)/radius);
+        }
+    }
+    float dist = distance(u_emitter.xy, v_texcoord);
+    if (dist < u_emitter.z) {
+        result.rg += u_amount * u_emitter.w * ((u_emitter.z - dist)/u_emitter.z);
+    }
+    gl_FragColor = result;
+}
\ No newline at end of file
@@ -0,0 +1,30 @@
+precision highp float;
+
+varying vec2 v_texcoord;
+
+uniform sampler2D u_target;
+uniform sampler2D u_impulses;
+uniform float u_count;
+uniform float u_radius;
+
+// must match MAX_IMPULSES in fluids.rs
+const int MAX_IMPULSES = 16;
+
+void main()
+{
+    vec4 result = texture2D(u_target, v_texcoord);
+    for (int i = 0; i < MAX_IMPULSES; i++) {
+        if (float(i) >= u_count) {
+            break;
+        }
+        float u = (float(i) + 0.5) / float(MAX_IMPULSES);
+        vec2 location = texture2D(u_impulses, vec2(u, 0.25)).xy;
+        // second row holds the colour to splat
+        vec3 color = texture2D(u_impulses, vec2(u, 0.75)).rgb;
+        float dist = distance(location, v_texcoord);
+        if (dist < u_radius) {
+            result += vec4(color * ((u_radius - dist)/u_radius), 0);
+        }
+    }
+    gl_FragColor = result;
+}
\ No newline at end of file
@@ -0,0 +1,22 @@
+precision highp float;
+
+varying vec2 v_texcoord;
+
+uniform sampler2D u_board;
+uniform sampler2D u_stamp;
+uniform vec2 u_board_size;
+// bottom left cell the stamp lands on
+uniform vec2 u_origin;
+uniform vec2 u_stamp_size;
+
+void main() {
+    vec2 cell = floor(v_texcoord * u_board_size);
+    // stamps wrap around the edges like the board does
+    vec2 local = mod(cell - u_origin, u_board_size);
+    if (local.x < u_stamp_size.x && local.y < u_stamp_size.y) {
+        // the stamp's cells are already in the board's format
+        gl_FragColor = texture2D(u_stamp, (local + 0.5) / u_stamp_size);
+    } else {
+        gl_FragColor = texture2D(u_board, v_texcoord);
+    }
+}
\ No newline at end of file
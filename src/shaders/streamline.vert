@@ -0,0 +1,32 @@
+attribute vec2 a_position;
+attribute vec2 a_instance;
+
+uniform sampler2D u_velocity;
+uniform float u_step_length;
+
+varying float v_alpha;
+
+// must be at least STREAMLINE_VERTICES in fluids.rs
+const int MAX_STEPS = 64;
+
+vec2 direction(in vec2 pos) {
+    vec2 velocity = texture2D(u_velocity, pos).xy;
+    float speed = length(velocity);
+    return speed > 0.0 ? velocity / speed : vec2(0.0);
+}
+
+// a_position.x is how many steps along the line this vertex is and
+// a_position.y how far along as a fraction, a_instance is the seed point
+void main() {
+    vec2 pos = a_instance;
+    for (int i = 0; i < MAX_STEPS; i++) {
+        if (float(i) >= a_position.x) {
+            break;
+        }
+        // midpoint method, plain euler spirals outwards around vortices
+        vec2 mid = pos + 0.5 * u_step_length * direction(pos);
+        pos += u_step_length * direction(mid);
+    }
+    gl_Position = vec4(pos * 2.0 - 1.0, 0.0, 1.0);
+    v_alpha = 0.9 * (1.0 - a_position.y);
+}
\ No newline at end of file
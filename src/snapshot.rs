@@ -0,0 +1,171 @@
+mod codec;
+
+use leptos::prelude::*;
+use utility::prelude::*;
+use web_sys::{HtmlInputElement, WebGl2RenderingContext, console};
+
+use crate::capture::download;
+use crate::controls::read_file;
+
+pub use codec::{DemoKind, Snapshot, TextureData, TextureFormat};
+
+type GL = WebGl2RenderingContext;
+
+impl TextureFormat {
+    // internal format, format and data type to hand to FromJsView::create
+    pub fn gl_formats(&self) -> (u32, u32, u32) {
+        match self {
+            Self::R8 => (GL::R8, GL::RED, GL::UNSIGNED_BYTE),
+            Self::Rg32F => (GL::RG32F, GL::RG, GL::FLOAT),
+            Self::Rgba32F => (GL::RGBA32F, GL::RGBA, GL::FLOAT),
+            Self::Rgba8 => (GL::RGBA8, GL::RGBA, GL::UNSIGNED_BYTE),
+        }
+    }
+}
+
+impl TextureData {
+    pub fn create_texture<T: FromJsView>(
+        &self,
+        context: &WebGl2RenderingContext,
+        tex_params: &[(u32, u32)],
+    ) -> T {
+        match self.format {
+            TextureFormat::R8 | TextureFormat::Rgba8 => {
+                self.create_from(context, tex_params, ArrayView::create(&self.bytes))
+            }
+            TextureFormat::Rg32F | TextureFormat::Rgba32F => {
+                let values = self.to_f32();
+                self.create_from(context, tex_params, ArrayView::create(&values))
+            }
+        }
+    }
+
+    fn create_from<T: FromJsView, V: JsView>(
+        &self,
+        context: &WebGl2RenderingContext,
+        tex_params: &[(u32, u32)],
+        data: V,
+    ) -> T {
+        let (internal_format, format, data_type) = self.format.gl_formats();
+        T::create(
+            context,
+            GL::TEXTURE_2D,
+            0,
+            internal_format,
+            self.width as i32,
+            self.height as i32,
+            0,
+            format,
+            data_type,
+            Some(data),
+            tex_params,
+        )
+    }
+}
+
+// Save and load requests shared between a demo's controls and its loop
+#[derive(Clone, Copy)]
+pub struct Snapshots {
+    pending_saves: RwSignal<u32>,
+    compress: RwSignal<bool>,
+    // bytes of a picked snapshot file waiting for the loop to load them
+    pending_load: RwSignal<Option<Vec<u8>>>,
+    // set by the autosave to ask the loop for a fresh session snapshot
+    session_due: RwSignal<bool>,
+    // the running demo's latest session snapshot, not yet written out
+    session: StoredValue<Option<Vec<u8>>>,
+}
+
+impl Snapshots {
+    pub fn new() -> Self {
+        Snapshots {
+            pending_saves: RwSignal::new(0),
+            compress: RwSignal::new(true),
+            pending_load: RwSignal::new(None),
+            session_due: RwSignal::new(false),
+            session: StoredValue::new(None),
+        }
+    }
+
+    // true once for every press of the save button
+    pub fn take_save(&self) -> bool {
+        if self.pending_saves.get_untracked() == 0 {
+            return false;
+        }
+        self.pending_saves.update_untracked(|saves| *saves -= 1);
+        true
+    }
+
+    pub fn take_load(&self) -> Option<Vec<u8>> {
+        self.pending_load.write_untracked().take()
+    }
+
+    // hands snapshot bytes to whichever demo loop is running next
+    pub fn load(&self, bytes: Vec<u8>) {
+        self.pending_load.set(Some(bytes));
+    }
+
+    pub fn request_session(&self) {
+        self.session_due.set(true);
+    }
+
+    // true when the loop should build a snapshot and pass it to store_session
+    pub fn session_due(&self) -> bool {
+        std::mem::take(&mut *self.session_due.write_untracked())
+    }
+
+    pub fn store_session(&self, snapshot: &Snapshot) {
+        match snapshot.encode(true) {
+            Ok(bytes) => self.session.set_value(Some(bytes)),
+            Err(err) => console::log_1(&format!("Failed to store session: {err}").into()),
+        }
+    }
+
+    // the session stored since the last call, if any
+    pub fn take_session(&self) -> Option<Vec<u8>> {
+        self.session.write_value().take()
+    }
+
+    pub fn save(&self, snapshot: &Snapshot, name: &str) {
+        match snapshot.encode(self.compress.get_untracked()) {
+            Ok(bytes) => download(
+                &bytes,
+                &format!("{name}.snapshot"),
+                "application/octet-stream",
+            ),
+            Err(err) => console::log_1(&format!("Failed to save snapshot: {err}").into()),
+        }
+    }
+}
+
+#[component]
+pub fn SnapshotControls(snapshots: Snapshots) -> impl IntoView {
+    view! {
+        <div style:margin="40px">
+            <button on:click=move |_| snapshots.pending_saves.update(|saves| *saves += 1)>
+                "Save state"
+            </button>
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=move || snapshots.compress.get()
+                    on:change=move |ev| snapshots.compress.set(event_target_checked(&ev))
+                />
+                " Compress"
+            </label>
+            <label>
+                " Load state "
+                <input
+                    type="file"
+                    accept=".snapshot"
+                    on:change=move |ev| {
+                        let input = event_target::<HtmlInputElement>(&ev);
+                        if let Some(file) = input.files().and_then(|files| files.get(0)) {
+                            read_file(file, snapshots.pending_load);
+                        }
+                    }
+                />
+            </label>
+        </div>
+    }
+}
@@ -0,0 +1,442 @@
+use std::fmt;
+
+// Layout, all integers little endian:
+//   magic, version: u16, kind: u8, flags: u8
+//   config length: u32, config JSON
+//   texture count: u16, then for each texture
+//     name length: u8, name, width: u32, height: u32, format: u8,
+//     payload length: u32, payload (deflated if FLAG_COMPRESSED is set)
+// payloads are the texture's channels packed row by row from the bottom
+const MAGIC: &[u8; 4] = b"CJSS";
+const VERSION: u16 = 1;
+const FLAG_COMPRESSED: u8 = 1;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DemoKind {
+    Fluids = 1,
+    Sand = 2,
+    Life = 3,
+}
+
+impl DemoKind {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(Self::Fluids),
+            2 => Some(Self::Sand),
+            3 => Some(Self::Life),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextureFormat {
+    R8 = 1,
+    Rg32F = 2,
+    Rgba32F = 3,
+    Rgba8 = 4,
+}
+
+impl TextureFormat {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(Self::R8),
+            2 => Some(Self::Rg32F),
+            3 => Some(Self::Rgba32F),
+            4 => Some(Self::Rgba8),
+            _ => None,
+        }
+    }
+
+    fn channels(&self) -> usize {
+        match self {
+            Self::R8 => 1,
+            Self::Rg32F => 2,
+            Self::Rgba32F | Self::Rgba8 => 4,
+        }
+    }
+
+    fn bytes_per_channel(&self) -> usize {
+        match self {
+            Self::R8 | Self::Rgba8 => 1,
+            Self::Rg32F | Self::Rgba32F => 4,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    BadMagic,
+    UnsupportedVersion(u16),
+    UnknownKind(u8),
+    UnknownFormat(u8),
+    Truncated,
+    BadPayload(String),
+    Config(serde_json::Error),
+    TooLong(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadMagic => write!(f, "not a snapshot"),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported version {version}"),
+            Self::UnknownKind(kind) => write!(f, "unknown demo kind {kind}"),
+            Self::UnknownFormat(format) => write!(f, "unknown texture format {format}"),
+            Self::Truncated => write!(f, "snapshot ends early"),
+            Self::BadPayload(name) => write!(f, "texture {name} has the wrong amount of data"),
+            Self::Config(err) => write!(f, "bad config: {err}"),
+            Self::TooLong(what) => write!(f, "{what} is too long to save"),
+        }
+    }
+}
+
+pub struct TextureData {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub format: TextureFormat,
+    pub bytes: Vec<u8>,
+}
+
+impl TextureData {
+    // keeps only the channels the format has from a float readback
+    pub fn from_rgba_f32(
+        name: &str,
+        width: i32,
+        height: i32,
+        format: TextureFormat,
+        rgba: &[f32],
+    ) -> Self {
+        let bytes = rgba
+            .chunks_exact(4)
+            .flat_map(|pixel| &pixel[..format.channels()])
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        TextureData {
+            name: name.to_string(),
+            width: width as u32,
+            height: height as u32,
+            format,
+            bytes,
+        }
+    }
+
+    // same for the byte readback of R8 and RGBA8 textures
+    pub fn from_rgba_u8(
+        name: &str,
+        width: i32,
+        height: i32,
+        format: TextureFormat,
+        rgba: &[u8],
+    ) -> Self {
+        TextureData {
+            name: name.to_string(),
+            width: width as u32,
+            height: height as u32,
+            format,
+            bytes: rgba
+                .chunks_exact(4)
+                .flat_map(|pixel| &pixel[..format.channels()])
+                .copied()
+                .collect(),
+        }
+    }
+
+    pub fn to_f32(&self) -> Vec<f32> {
+        self.bytes
+            .chunks_exact(4)
+            .map(|value| f32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+            .collect()
+    }
+
+    // None when the header claims more than fits in memory
+    fn expected_len(&self) -> Option<usize> {
+        (self.width as usize)
+            .checked_mul(self.height as usize)?
+            .checked_mul(self.format.channels())?
+            .checked_mul(self.format.bytes_per_channel())
+    }
+}
+
+pub struct Snapshot {
+    pub kind: DemoKind,
+    // demo settings as JSON, each demo decides what goes in here
+    pub config: String,
+    pub textures: Vec<TextureData>,
+}
+
+impl Snapshot {
+    pub fn texture(&self, name: &str) -> Option<&TextureData> {
+        self.textures.iter().find(|texture| texture.name == name)
+    }
+
+    pub fn config<T: serde::de::DeserializeOwned>(&self) -> Result<T, SnapshotError> {
+        serde_json::from_str(&self.config).map_err(SnapshotError::Config)
+    }
+
+    // fails rather than truncating anything that doesn't fit its length field
+    pub fn encode(&self, compress: bool) -> Result<Vec<u8>, SnapshotError> {
+        let too_long = |what: &str| SnapshotError::TooLong(what.to_string());
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.push(self.kind as u8);
+        out.push(if compress { FLAG_COMPRESSED } else { 0 });
+        let config_len = u32::try_from(self.config.len()).map_err(|_| too_long("config"))?;
+        out.extend_from_slice(&config_len.to_le_bytes());
+        out.extend_from_slice(self.config.as_bytes());
+        let count = u16::try_from(self.textures.len()).map_err(|_| too_long("texture list"))?;
+        out.extend_from_slice(&count.to_le_bytes());
+        for texture in &self.textures {
+            let name_len = u8::try_from(texture.name.len())
+                .map_err(|_| too_long(&format!("texture name {}", texture.name)))?;
+            out.push(name_len);
+            out.extend_from_slice(texture.name.as_bytes());
+            out.extend_from_slice(&texture.width.to_le_bytes());
+            out.extend_from_slice(&texture.height.to_le_bytes());
+            out.push(texture.format as u8);
+            let payload = if compress {
+                miniz_oxide::deflate::compress_to_vec(&texture.bytes, 6)
+            } else {
+                texture.bytes.clone()
+            };
+            let payload_len = u32::try_from(payload.len())
+                .map_err(|_| too_long(&format!("texture {}", texture.name)))?;
+            out.extend_from_slice(&payload_len.to_le_bytes());
+            out.extend_from_slice(&payload);
+        }
+        Ok(out)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let kind = reader.u8()?;
+        let kind = DemoKind::from_u8(kind).ok_or(SnapshotError::UnknownKind(kind))?;
+        let compressed = reader.u8()? & FLAG_COMPRESSED != 0;
+        let config_len = reader.u32()? as usize;
+        let config = String::from_utf8_lossy(reader.take(config_len)?).into_owned();
+
+        let count = reader.u16()?;
+        let mut textures = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let name_len = reader.u8()? as usize;
+            let name = String::from_utf8_lossy(reader.take(name_len)?).into_owned();
+            let width = reader.u32()?;
+            let height = reader.u32()?;
+            let format = reader.u8()?;
+            let format =
+                TextureFormat::from_u8(format).ok_or(SnapshotError::UnknownFormat(format))?;
+            let payload_len = reader.u32()? as usize;
+            let payload = reader.take(payload_len)?;
+
+            let mut texture = TextureData {
+                name,
+                width,
+                height,
+                format,
+                bytes: Vec::new(),
+            };
+            let Some(expected_len) = texture.expected_len() else {
+                return Err(SnapshotError::BadPayload(texture.name));
+            };
+            texture.bytes = if compressed {
+                miniz_oxide::inflate::decompress_to_vec_with_limit(payload, expected_len)
+                    .map_err(|_| SnapshotError::BadPayload(texture.name.clone()))?
+            } else {
+                payload.to_vec()
+            };
+            if texture.bytes.len() != expected_len {
+                return Err(SnapshotError::BadPayload(texture.name));
+            }
+            textures.push(texture);
+        }
+
+        Ok(Snapshot {
+            kind,
+            config,
+            textures,
+        })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        let end = self.pos.checked_add(len).ok_or(SnapshotError::Truncated)?;
+        let taken = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(SnapshotError::Truncated)?;
+        self.pos = end;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SnapshotError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Snapshot {
+        Snapshot {
+            kind: DemoKind::Life,
+            config: r#"{"rule":"B3/S23"}"#.to_string(),
+            textures: vec![
+                TextureData::from_rgba_u8(
+                    "board",
+                    2,
+                    1,
+                    TextureFormat::R8,
+                    &[1, 2, 3, 4, 5, 6, 7, 8],
+                ),
+                TextureData::from_rgba_f32(
+                    "velocity",
+                    1,
+                    2,
+                    TextureFormat::Rg32F,
+                    &[0.5, -1.0, 9.0, 9.0, 2.0, 0.25, 9.0, 9.0],
+                ),
+            ],
+        }
+    }
+
+    fn assert_sample(snapshot: &Snapshot) {
+        assert_eq!(snapshot.kind, DemoKind::Life);
+        assert_eq!(snapshot.config, r#"{"rule":"B3/S23"}"#);
+        assert_eq!(snapshot.textures.len(), 2);
+        let board = snapshot.texture("board").unwrap();
+        assert_eq!((board.width, board.height), (2, 1));
+        assert_eq!(board.format, TextureFormat::R8);
+        assert_eq!(board.bytes, [1, 5]);
+        let velocity = snapshot.texture("velocity").unwrap();
+        assert_eq!(velocity.format, TextureFormat::Rg32F);
+        assert_eq!(velocity.to_f32(), [0.5, -1.0, 2.0, 0.25]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = sample().encode(false).unwrap();
+        assert_sample(&Snapshot::decode(&bytes).unwrap());
+    }
+
+    #[test]
+    fn compressed_round_trip() {
+        let bytes = sample().encode(true).unwrap();
+        assert_sample(&Snapshot::decode(&bytes).unwrap());
+    }
+
+    #[test]
+    fn truncated() {
+        for compress in [false, true] {
+            let bytes = sample().encode(compress).unwrap();
+            for len in 0..bytes.len() {
+                assert!(
+                    matches!(
+                        Snapshot::decode(&bytes[..len]),
+                        Err(SnapshotError::Truncated | SnapshotError::BadMagic)
+                    ),
+                    "decoded {len} of {} bytes",
+                    bytes.len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bad_magic() {
+        let mut bytes = sample().encode(false).unwrap();
+        bytes[0] = b'X';
+        assert!(matches!(
+            Snapshot::decode(&bytes),
+            Err(SnapshotError::BadMagic)
+        ));
+    }
+
+    #[test]
+    fn bad_version() {
+        let mut bytes = sample().encode(false).unwrap();
+        bytes[4..6].copy_from_slice(&2u16.to_le_bytes());
+        assert!(matches!(
+            Snapshot::decode(&bytes),
+            Err(SnapshotError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn bad_kind() {
+        let mut bytes = sample().encode(false).unwrap();
+        bytes[6] = 9;
+        assert!(matches!(
+            Snapshot::decode(&bytes),
+            Err(SnapshotError::UnknownKind(9))
+        ));
+    }
+
+    #[test]
+    fn wrong_payload_size() {
+        let mut snapshot = sample();
+        snapshot.textures[0].bytes.push(0);
+        let bytes = snapshot.encode(false).unwrap();
+        assert!(matches!(
+            Snapshot::decode(&bytes),
+            Err(SnapshotError::BadPayload(name)) if name == "board"
+        ));
+    }
+
+    #[test]
+    fn huge_header() {
+        let mut snapshot = sample();
+        snapshot.textures[1].width = u32::MAX;
+        snapshot.textures[1].height = u32::MAX;
+        for compress in [false, true] {
+            let bytes = snapshot.encode(compress).unwrap();
+            assert!(matches!(
+                Snapshot::decode(&bytes),
+                Err(SnapshotError::BadPayload(name)) if name == "velocity"
+            ));
+        }
+    }
+
+    #[test]
+    fn long_name() {
+        let mut snapshot = sample();
+        snapshot.textures[0].name = "x".repeat(255);
+        let bytes = snapshot.encode(false).unwrap();
+        assert!(
+            Snapshot::decode(&bytes)
+                .unwrap()
+                .texture(&"x".repeat(255))
+                .is_some()
+        );
+        snapshot.textures[0].name = "x".repeat(256);
+        assert!(matches!(
+            snapshot.encode(false),
+            Err(SnapshotError::TooLong(_))
+        ));
+    }
+}
@@ -0,0 +1,101 @@
+use leptos::prelude::*;
+use leptos_use::use_event_listener;
+use utility::prelude::*;
+
+const SPEEDS: [f64; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+
+// Playback state shared between a demo's controls and its animation loop,
+// the loop asks it how much simulated time has passed instead of reading
+// the wall clock directly
+#[derive(Clone, Copy)]
+pub struct SimClock {
+    playing: RwSignal<bool>,
+    speed: RwSignal<f64>,
+    pending_steps: RwSignal<u32>,
+}
+
+impl SimClock {
+    pub fn new() -> Self {
+        SimClock {
+            playing: RwSignal::new(true),
+            speed: RwSignal::new(1.0),
+            pending_steps: RwSignal::new(0),
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing.get_untracked()
+    }
+
+    // scales real elapsed time into simulated time, nothing passes while paused
+    pub fn advance(&self, elapsed: f64) -> f64 {
+        if self.is_playing() {
+            elapsed * self.speed.get_untracked()
+        } else {
+            0.0
+        }
+    }
+
+    // true once for every press of the step button
+    pub fn take_step(&self) -> bool {
+        if self.pending_steps.get_untracked() == 0 {
+            return false;
+        }
+        self.pending_steps.update_untracked(|steps| *steps -= 1);
+        true
+    }
+}
+
+// Hands a demo's animation loop to the reactive owner it was started under,
+// it's paused while the page is hidden and dropped, stopping it, when the
+// owner is cleaned up
+pub fn own_loop(animation: AnimationLoop) {
+    let animation = StoredValue::new_local(animation);
+    let _ = use_event_listener(document(), leptos::ev::visibilitychange, move |_| {
+        animation.try_with_value(|animation| {
+            if document().hidden() {
+                animation.pause();
+            } else {
+                animation.resume();
+            }
+        });
+    });
+}
+
+#[component]
+pub fn Transport(clock: SimClock) -> impl IntoView {
+    let speeds = SPEEDS
+        .iter()
+        .enumerate()
+        .map(|(idx, speed)| {
+            view! {
+                <option value=idx.to_string() selected=move || clock.speed.get() == *speed>
+                    {format!("{speed}×")}
+                </option>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <div style:margin="40px">
+            <button on:click=move |_| clock.playing.update(|playing| *playing = !*playing)>
+                {move || if clock.playing.get() { "Pause" } else { "Play" }}
+            </button>
+            <button
+                disabled=move || clock.playing.get()
+                on:click=move |_| clock.pending_steps.update(|steps| *steps += 1)
+            >
+                "Step"
+            </button>
+            <select on:change=move |ev| {
+                if let Some(speed) = event_target_value(&ev)
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|idx| SPEEDS.get(idx))
+                {
+                    clock.speed.set(*speed);
+                }
+            }>{speeds}</select>
+        </div>
+    }
+}